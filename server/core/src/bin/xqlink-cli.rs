@@ -0,0 +1,330 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use serde::Serialize;
+use xqlink_core::chess;
+use xqlink_core::engine::chessdb;
+use xqlink_core::engine::Engine;
+use xqlink_core::engine::EngineConfig;
+use xqlink_core::engine::QueryResult;
+use xqlink_core::engine::QueryState;
+use xqlink_core::recognize;
+
+const USAGE: &str = "用法: xqlink-cli [选项] <图片>
+      xqlink-cli [选项] --fen <FEN>
+      xqlink-cli --perft <N> --fen <FEN>
+
+选项:
+  --fen <FEN>          分析指定局面, 不做图片识别
+  --turn <w|b>         识别出的局面的行棋方, 默认为红方
+  --recognize          只识别局面, 不分析
+  --move <着法>        把ICCS、WXF或中文着法解析为ICCS, 不分析
+  --chessdb-only       只查询云库
+  --engine-only        不查询云库, 直接调用引擎
+  --engine <DIR>       Pikafish所在目录, 默认为 libs/pikafish
+  --depth <N>          引擎搜索深度
+  --time <MS>          引擎搜索时间(毫秒)
+  --multipv <N>        引擎给出的候选着法数量
+  --notation <LOCALE>  中文记谱: simplified, traditional, english
+  --json               以JSON格式输出
+  --perft <N>          对比本地走法生成与引擎 go perft 的结果
+  -h, --help           显示帮助";
+
+// 引擎perft的最长等待时间
+const PERFT_TIMEOUT: Duration = Duration::from_secs(600);
+
+// 命令行参数
+struct Args {
+    image: Option<String>,
+    fen: Option<String>,
+    turn: chess::Color,
+    recognize_only: bool,
+    mv: Option<String>,
+    chessdb: bool,
+    engine: bool,
+    engine_dir: PathBuf,
+    config: EngineConfig,
+    notation: chess::Locale,
+    json: bool,
+    perft: Option<usize>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut parsed = Self {
+            image: None,
+            fen: None,
+            turn: chess::Color::Red,
+            recognize_only: false,
+            mv: None,
+            chessdb: true,
+            engine: true,
+            engine_dir: PathBuf::from("libs/pikafish"),
+            config: EngineConfig::default(),
+            notation: chess::Locale::default(),
+            json: false,
+            perft: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} 缺少参数值", arg));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--fen" => parsed.fen = Some(value()?),
+                "--turn" => {
+                    parsed.turn = match value()?.as_str() {
+                        "w" | "r" => chess::Color::Red,
+                        "b" => chess::Color::Black,
+                        turn => return Err(format!("未知的行棋方: {}", turn)),
+                    }
+                }
+                "--recognize" => parsed.recognize_only = true,
+                "--move" => parsed.mv = Some(value()?),
+                "--chessdb-only" => parsed.engine = false,
+                "--engine-only" => parsed.chessdb = false,
+                "--engine" => parsed.engine_dir = PathBuf::from(value()?),
+                "--depth" => parsed.config.depth = value()?.parse().map_err(|_| "--depth 应为整数")?,
+                "--time" => parsed.config.time = value()?.parse().map_err(|_| "--time 应为整数")?,
+                "--multipv" => parsed.config.multipv = value()?.parse().map_err(|_| "--multipv 应为整数")?,
+                "--notation" => {
+                    parsed.notation = match value()?.to_lowercase().as_str() {
+                        "simplified" => chess::Locale::Simplified,
+                        "traditional" => chess::Locale::Traditional,
+                        "english" => chess::Locale::English,
+                        locale => return Err(format!("未知的记谱语言: {}", locale)),
+                    }
+                }
+                "--json" => parsed.json = true,
+                "--perft" => parsed.perft = Some(value()?.parse().map_err(|_| "--perft 应为整数")?),
+                _ if arg.starts_with('-') => return Err(format!("未知的选项: {}", arg)),
+                _ => parsed.image = Some(arg),
+            }
+        }
+        if parsed.image.is_none() == parsed.fen.is_none() {
+            return Err("需要指定图片或 --fen 其中之一".to_string());
+        }
+        if !parsed.chessdb && !parsed.engine {
+            return Err("--chessdb-only 与 --engine-only 不能同时使用".to_string());
+        }
+        Ok(Some(parsed))
+    }
+}
+
+// 输出结果
+#[derive(Serialize)]
+struct Output {
+    fen: String,
+    camp: Option<chess::Camp>,         // 图片识别出的我方阵营
+    mv: Option<String>,                // --move 解析出的着法(ICCS)
+    gameover: Option<chess::GameOver>, // 对局已结束
+    analysis: Option<QueryResult>,     // 分析结果
+}
+
+// 识别图片或解析FEN, 得到待分析的局面
+fn load_board(args: &Args) -> Result<(Option<chess::Camp>, chess::Board), String> {
+    if let Some(fen) = &args.fen {
+        let board: chess::Board = fen.parse()?;
+        board.check_positions()?;
+        return Ok((None, board));
+    }
+    let image = recognize::ImageSource::Path(args.image.clone().unwrap()).load()?;
+    let (camp, board) = recognize::recognize(image)?;
+    Ok((Some(camp), board.with_turn(args.turn)))
+}
+
+// 先查询云库, 没有结果时调用引擎, 云库只给出一个着法, 需要多条候选时直接调用引擎
+async fn search(args: &Args, board: &chess::Board) -> Result<QueryResult, String> {
+    let fen = board.to_string();
+    if args.chessdb && (args.config.multipv <= 1 || !args.engine) {
+        let result = chessdb::query(&fen, args.config.chessdb_timeout).await;
+        match result.state {
+            QueryState::Success => return Ok(result),
+            QueryState::InvalidBoard => return Err("云库: 无效的局面".to_string()),
+            _ if !args.engine => return Err(format!("云库没有结果: {:?}", result.state)),
+            _ => {}
+        }
+    }
+    if !args.engine_dir.is_dir() {
+        return Err(format!("引擎目录不存在: {}", args.engine_dir.display()));
+    }
+    let mut engine = Engine::new(&args.engine_dir);
+    engine.set_threads(args.config.threads);
+    engine.set_hash(args.config.hash);
+    let config = EngineConfig { chessdb_enabled: false, ..args.config };
+    engine.search(&fen, &config).await.ok_or_else(|| "引擎没有结果".to_string())
+}
+
+// 翻译主要变例和各候选变化
+fn translate(board: &chess::Board, result: &mut QueryResult, locale: chess::Locale) {
+    (result.moves, result.wxf) = chess::translate_pv(board, &result.pvs, locale);
+    for candidate in result.candidates.iter_mut() {
+        (candidate.moves, candidate.wxf) = chess::translate_pv(board, &candidate.pvs, locale);
+    }
+}
+
+async fn run(args: &Args) -> Result<Output, String> {
+    let (camp, board) = load_board(args)?;
+    let mut output = Output { fen: board.to_string(), camp, mv: None, gameover: None, analysis: None };
+    if args.recognize_only {
+        return Ok(output);
+    }
+    if let Some(text) = &args.mv {
+        output.mv = Some(chess::parse_move(&board, text)?.to_string());
+        return Ok(output);
+    }
+    output.gameover = chess::movegen::game_over(&board);
+    if output.gameover.is_some() {
+        return Ok(output);
+    }
+    let mut result = search(args, &board).await?;
+    translate(&board, &mut result, args.notation);
+    output.analysis = Some(result);
+    Ok(output)
+}
+
+// perft对比结果
+#[derive(Serialize)]
+struct PerftOutput {
+    fen: String,
+    depth: usize,
+    ours: u64,                // 本地的节点数
+    theirs: u64,              // 引擎的节点数
+    diff: Vec<PerftMismatch>, // 节点数不一致的根节点走法
+}
+
+#[derive(Serialize)]
+struct PerftMismatch {
+    mv: String,
+    ours: Option<u64>,   // 本地没有这个走法时为空
+    theirs: Option<u64>, // 引擎没有这个走法时为空
+}
+
+// 逐个根节点走法对比本地与引擎的perft结果
+async fn run_perft(args: &Args, depth: usize) -> Result<PerftOutput, String> {
+    let (_, board) = load_board(args)?;
+    if !args.engine_dir.is_dir() {
+        return Err(format!("引擎目录不存在: {}", args.engine_dir.display()));
+    }
+    let ours = chess::perft::divide(&board, depth);
+    let theirs = Engine::new(&args.engine_dir).perft(&board.to_string(), depth, PERFT_TIMEOUT).await?;
+    let total = |result: &[(String, u64)]| result.iter().map(|(_, nodes)| nodes).sum::<u64>();
+    let diff = chess::perft::divide_diff(&ours, &theirs)
+        .into_iter()
+        .map(|(mv, ours, theirs)| PerftMismatch { mv, ours, theirs })
+        .collect();
+    Ok(PerftOutput { fen: board.to_string(), depth, ours: total(&ours), theirs: total(&theirs), diff })
+}
+
+fn print_perft(output: &PerftOutput) {
+    println!("perft {}: 本地 {} 引擎 {}", output.depth, output.ours, output.theirs);
+    let nodes = |nodes: Option<u64>| nodes.map_or("-".to_string(), |nodes| nodes.to_string());
+    for mismatch in &output.diff {
+        println!("{}: 本地 {} 引擎 {}", mismatch.mv, nodes(mismatch.ours), nodes(mismatch.theirs));
+    }
+}
+
+fn print_text(output: &Output) {
+    println!("FEN: {}", output.fen);
+    if let Some(camp) = &output.camp {
+        println!("阵营: {:?}", camp);
+    }
+    if let Some(mv) = &output.mv {
+        println!("着法: {}", mv);
+    }
+    if let Some(gameover) = &output.gameover {
+        println!("对局结束: {:?} {:?}", gameover.winner, gameover.reason);
+    }
+    if let Some(result) = &output.analysis {
+        println!("来源: {} 深度: {} 得分: {}", result.source, result.depth, result.score);
+        println!("ICCS: {}", result.pvs.join(" "));
+        println!("中文: {}", result.moves.join(" "));
+        println!("WXF: {}", result.wxf.join(" "));
+        // 多条候选变化时逐条列出
+        if result.candidates.len() > 1 {
+            for (i, candidate) in result.candidates.iter().enumerate() {
+                println!("{}. [{} {}] {}", i + 1, candidate.depth, candidate.score, candidate.moves.join(" "));
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(depth) = args.perft {
+        let output = match run_perft(&args, depth).await {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            print_perft(&output);
+        }
+        // 结果不一致时以失败退出, 便于脚本判断
+        if !output.diff.is_empty() {
+            eprintln!("{}个走法的结果不一致", output.diff.len());
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+    match run(&args).await {
+        Ok(output) if args.json => println!("{}", serde_json::to_string_pretty(&output).unwrap()),
+        Ok(output) => print_text(&output),
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Option<Args>, String> { Args::parse(args.split_whitespace().map(String::from)) }
+
+    #[test]
+    fn test_args() {
+        let args = parse("--fen 4k4/9/9/9/9/9/9/9/9/4K4 --engine-only --depth 12 --multipv 3 --json").unwrap().unwrap();
+        assert_eq!(args.fen.as_deref(), Some("4k4/9/9/9/9/9/9/9/9/4K4"));
+        assert!(!args.chessdb && args.engine && args.json);
+        assert_eq!((args.config.depth, args.config.multipv), (12, 3));
+
+        let args = parse("board.png --turn b --notation English --chessdb-only").unwrap().unwrap();
+        assert_eq!(args.image.as_deref(), Some("board.png"));
+        assert_eq!(args.turn, chess::Color::Black);
+        assert_eq!(args.notation, chess::Locale::English);
+        assert!(args.chessdb && !args.engine);
+
+        let args = parse("--perft 3 --fen 4k4/9/9/9/9/9/9/9/9/4K4 --json").unwrap().unwrap();
+        assert_eq!(args.perft, Some(3));
+        assert!(args.json);
+        let args = parse("--fen 4k4/9/9/9/9/9/9/9/9/4K4 --move 帅五平四").unwrap().unwrap();
+        assert_eq!(args.mv.as_deref(), Some("帅五平四"));
+
+        assert!(parse("--perft x --fen 4k4/9/9/9/9/9/9/9/9/4K4").is_err());
+
+        assert!(parse("--help").unwrap().is_none());
+        assert!(parse("").is_err());
+        assert!(parse("board.png --fen 9/9").is_err());
+        assert!(parse("board.png --depth").is_err());
+        assert!(parse("board.png --chessdb-only --engine-only").is_err());
+        assert!(parse("board.png --unknown").is_err());
+    }
+}
@@ -0,0 +1,401 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde::Serializer;
+use tracing::warn;
+
+use super::get_piece_name;
+use super::zobrist::piece_key;
+use super::zobrist::placement_key;
+use super::zobrist::SIDE_KEY;
+
+pub const START_FEN: &str = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+
+// 阵营, 红方在下
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Color {
+    Red,
+    Black,
+}
+
+impl Color {
+    pub fn opponent(self) -> Self {
+        match self {
+            Color::Red => Color::Black,
+            Color::Black => Color::Red,
+        }
+    }
+
+    // FEN中的行棋方
+    pub fn to_char(self) -> char {
+        match self {
+            Color::Red => 'w',
+            Color::Black => 'b',
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Color::Red => "红方",
+            Color::Black => "黑方",
+        }
+    }
+}
+
+// 兵种
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+    King,
+    Advisor,
+    Bishop,
+    Knight,
+    Rook,
+    Cannon,
+    Pawn,
+}
+
+impl PieceKind {
+    pub const ALL: [PieceKind; 7] = [
+        PieceKind::King,
+        PieceKind::Advisor,
+        PieceKind::Bishop,
+        PieceKind::Knight,
+        PieceKind::Rook,
+        PieceKind::Cannon,
+        PieceKind::Pawn,
+    ];
+
+    // 每方的最大数量
+    pub fn limit(self) -> usize {
+        match self {
+            PieceKind::King => 1,
+            PieceKind::Pawn => 5,
+            _ => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Piece {
+    pub color: Color,
+    pub kind: PieceKind,
+}
+
+impl Piece {
+    pub const fn new(color: Color, kind: PieceKind) -> Self { Self { color, kind } }
+
+    // FEN字符转换为棋子, 大写为红方
+    pub fn from_char(c: char) -> Option<Self> {
+        let kind = match c.to_ascii_lowercase() {
+            'k' => PieceKind::King,
+            'a' => PieceKind::Advisor,
+            'b' => PieceKind::Bishop,
+            'n' => PieceKind::Knight,
+            'r' => PieceKind::Rook,
+            'c' => PieceKind::Cannon,
+            'p' => PieceKind::Pawn,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() { Color::Red } else { Color::Black };
+        Some(Self { color, kind })
+    }
+
+    pub fn to_char(self) -> char {
+        let c = match self.kind {
+            PieceKind::King => 'k',
+            PieceKind::Advisor => 'a',
+            PieceKind::Bishop => 'b',
+            PieceKind::Knight => 'n',
+            PieceKind::Rook => 'r',
+            PieceKind::Cannon => 'c',
+            PieceKind::Pawn => 'p',
+        };
+        match self.color {
+            Color::Red => c.to_ascii_uppercase(),
+            Color::Black => c,
+        }
+    }
+
+    // 判断棋子能否出现在该位置(将、士、象、兵有固定的落点)
+    pub fn can_stand(self, sq: Square) -> bool {
+        // 统一换算为红方视角
+        let (x, y) = match self.color {
+            Color::Red => (sq.x(), sq.y()),
+            Color::Black => (8 - sq.x(), 9 - sq.y()),
+        };
+        match self.kind {
+            PieceKind::King => (3..=5).contains(&x) && y >= 7,
+            PieceKind::Advisor => matches!((x, y), (3, 7) | (5, 7) | (4, 8) | (3, 9) | (5, 9)),
+            PieceKind::Bishop => matches!((x, y), (2, 5) | (6, 5) | (0, 7) | (4, 7) | (8, 7) | (2, 9) | (6, 9)),
+            // 未过河的兵只能在自己的兵线上
+            PieceKind::Pawn => y <= 4 || (y <= 6 && x % 2 == 0),
+            _ => true,
+        }
+    }
+}
+
+impl Serialize for Piece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.to_char())
+    }
+}
+
+// 棋盘坐标, x为纵线(0-8, 对应ICCS的a-i), y为横线(0-9, 0为黑方底线)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    pub fn new(x: usize, y: usize) -> Self {
+        debug_assert!(x < 9 && y < 10);
+        Self((y * 9 + x) as u8)
+    }
+
+    pub fn x(self) -> usize { self.0 as usize % 9 }
+
+    pub fn y(self) -> usize { self.0 as usize / 9 }
+
+    pub fn index(self) -> usize { self.0 as usize }
+
+    pub fn offset(self, dx: isize, dy: isize) -> Option<Self> {
+        let x = self.x().checked_add_signed(dx)?;
+        let y = self.y().checked_add_signed(dy)?;
+        if x < 9 && y < 10 {
+            Some(Self::new(x, y))
+        } else {
+            None
+        }
+    }
+
+    // 按a9到i0的顺序遍历全部90个位置
+    pub fn all() -> impl Iterator<Item = Square> { (0..90).map(Square) }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(super::BOARD_MAP[self.y()][self.x()]) }
+}
+
+impl FromStr for Square {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[file @ b'a'..=b'i', rank @ b'0'..=b'9'] => Ok(Self::new((file - b'a') as usize, (b'9' - rank) as usize)),
+            _ => Err(format!("无效的坐标: {}", s)),
+        }
+    }
+}
+
+impl Serialize for Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> { serializer.collect_str(self) }
+}
+
+// 走法, 以ICCS格式表示, 如 h2e2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Self { Self { from, to } }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}{}", self.from, self.to) }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 4 || !s.is_ascii() {
+            return Err(format!("无效的走法: {}", s));
+        }
+        let (from, to) = s.split_at(2);
+        Ok(Self { from: from.parse()?, to: to.parse()? })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Board {
+    squares: [Option<Piece>; 90],
+    // 棋子摆放的Zobrist键, 随set增量更新, 不含行棋方
+    key: u64,
+    pub turn: Color,
+    // 距离上次吃子的半回合数
+    pub halfmove: u32,
+    // 回合数, 黑方走完后加一
+    pub fullmove: u32,
+}
+
+impl Default for Board {
+    fn default() -> Self { Self { squares: [None; 90], key: 0, turn: Color::Red, halfmove: 0, fullmove: 1 } }
+}
+
+impl Board {
+    pub fn startpos() -> Self { START_FEN.parse().unwrap() }
+
+    pub fn get(&self, sq: Square) -> Option<Piece> { self.squares[sq.index()] }
+
+    pub fn set(&mut self, sq: Square, piece: Option<Piece>) {
+        if let Some(old) = self.squares[sq.index()] {
+            self.key ^= piece_key(old, sq);
+        }
+        if let Some(new) = piece {
+            self.key ^= piece_key(new, sq);
+        }
+        self.squares[sq.index()] = piece;
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        Square::all().filter_map(|sq| self.get(sq).map(|piece| (sq, piece)))
+    }
+
+    pub fn king(&self, color: Color) -> Option<Square> {
+        let king = Piece::new(color, PieceKind::King);
+        Square::all().find(|&sq| self.get(sq) == Some(king))
+    }
+
+    pub fn with_turn(mut self, turn: Color) -> Self {
+        self.turn = turn;
+        self
+    }
+
+    // 执行走法并交换行棋方, 不检查合法性
+    pub fn make_move(&self, mv: Move) -> Self {
+        let mut board = *self;
+        let piece = board.get(mv.from);
+        let captured = board.get(mv.to);
+        board.set(mv.from, None);
+        board.set(mv.to, piece);
+        board.halfmove = if captured.is_some() { 0 } else { self.halfmove + 1 };
+        if self.turn == Color::Black {
+            board.fullmove += 1;
+        }
+        board.turn = self.turn.opponent();
+        board
+    }
+
+    // 只比较棋子摆放, 不比较行棋方和计数
+    pub fn same_placement(&self, other: &Board) -> bool { self.squares == other.squares }
+
+    // 局面的Zobrist键, 包含棋子摆放和行棋方, 不含计数
+    pub fn zobrist(&self) -> u64 {
+        match self.turn {
+            Color::Red => self.key,
+            Color::Black => self.key ^ SIDE_KEY,
+        }
+    }
+
+    pub fn is_startpos(&self) -> bool { self.same_placement(&Board::startpos()) }
+
+    // 旋转180度
+    pub fn rotate(&mut self) {
+        self.squares.reverse();
+        self.key = placement_key(self);
+    }
+
+    // 检测棋子数量和落点是否合法
+    pub fn is_valid(&self) -> bool {
+        match self.check_counts().and_then(|_| self.check_positions()) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{}", e);
+                false
+            }
+        }
+    }
+
+    // 检测双方各有一个将帅, 且各兵种不超过上限
+    pub fn check_counts(&self) -> Result<(), String> {
+        let mut counts = [[0usize; 7]; 2];
+        for (_, piece) in self.pieces() {
+            counts[piece.color as usize][piece.kind as usize] += 1;
+        }
+        for kind in PieceKind::ALL {
+            let red = counts[Color::Red as usize][kind as usize];
+            let black = counts[Color::Black as usize][kind as usize];
+            let missing = kind == PieceKind::King && (red == 0 || black == 0);
+            if missing || red > kind.limit() || black > kind.limit() {
+                let name = get_piece_name(Piece::new(Color::Black, kind));
+                return Err(format!("黑方或红方'{}'超出合法数量(红:{}, 黑:{})", name, red, black));
+            }
+        }
+        Ok(())
+    }
+
+    // 检测将、士、象、兵是否在合法落点
+    pub fn check_positions(&self) -> Result<(), String> {
+        match self.pieces().find(|(sq, piece)| !piece.can_stand(*sq)) {
+            Some((sq, piece)) => Err(format!(
+                "{}'{}'不在合法位置内, ({}行{}列)",
+                piece.color.name(),
+                get_piece_name(piece),
+                sq.y(),
+                sq.x()
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let sq: Square = "h2".parse().unwrap();
+        assert_eq!((sq.x(), sq.y()), (7, 7));
+        assert_eq!(sq.to_string(), "h2");
+        assert_eq!(sq.offset(2, 0), None);
+        assert!("j0".parse::<Square>().is_err());
+        assert!("a".parse::<Square>().is_err());
+    }
+
+    #[test]
+    fn test_move() {
+        let mv: Move = "h2e2".parse().unwrap();
+        assert_eq!(mv.to_string(), "h2e2");
+        assert!("h2e".parse::<Move>().is_err());
+        assert!("h2e=".parse::<Move>().is_err());
+        assert!("炮二平五".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn test_make_move() {
+        let board = Board::startpos();
+        let board = board.make_move("h2e2".parse().unwrap());
+        assert_eq!(board.turn, Color::Black);
+        assert_eq!((board.halfmove, board.fullmove), (1, 1));
+        let board = board.make_move("h7e7".parse().unwrap());
+        assert_eq!((board.halfmove, board.fullmove), (2, 2));
+        let board = board.make_move("e2e6".parse().unwrap());
+        assert_eq!(board.halfmove, 0);
+        assert_eq!(board.get("e6".parse().unwrap()), Piece::from_char('C'));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(Board::startpos().is_valid());
+        // 象走到了士的位置
+        let mut board = Board::startpos();
+        board.set("g9".parse().unwrap(), None);
+        board.set("e8".parse().unwrap(), Piece::from_char('b'));
+        assert!(board.check_counts().is_ok());
+        assert!(board.check_positions().is_err());
+        assert!(!board.is_valid());
+        // 缺少帅
+        let mut board = Board::startpos();
+        board.set("e0".parse().unwrap(), None);
+        assert!(board.check_counts().is_err());
+        // 三个士
+        let mut board = Board::startpos();
+        board.set("g9".parse().unwrap(), Piece::from_char('a'));
+        assert!(board.check_counts().is_err());
+        // 未过河的兵不能横向移动
+        let board: Board = "4k4/9/9/9/9/9/1P7/9/9/4K4 w".parse().unwrap();
+        assert!(!board.is_valid());
+    }
+}
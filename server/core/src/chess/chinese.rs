@@ -0,0 +1,326 @@
+use super::get_piece_name;
+use super::get_verticals;
+use super::notation::action_of;
+use super::notation::file_to_x;
+use super::notation::resolve;
+use super::notation::tandem;
+use super::notation::x_to_file;
+use super::notation::Action;
+use super::notation::Mover;
+use super::notation::Order;
+use super::wxf::board_move_wxf;
+use super::Board;
+use super::Color;
+use super::Locale;
+use super::Move;
+use super::Piece;
+use super::PieceKind;
+
+// 按语言取棋子名称, 繁体红方使用带人字旁的写法
+fn piece_name(piece: Piece, locale: Locale) -> char {
+    match (locale, piece.color, piece.kind) {
+        (Locale::Traditional, Color::Red, PieceKind::King) => '帥',
+        (Locale::Traditional, Color::Black, PieceKind::King) => '將',
+        (Locale::Traditional, Color::Red, PieceKind::Knight) => '傌',
+        (Locale::Traditional, Color::Black, PieceKind::Knight) => '馬',
+        (Locale::Traditional, Color::Red, PieceKind::Rook) => '俥',
+        (Locale::Traditional, Color::Black, PieceKind::Rook) => '車',
+        (Locale::Traditional, Color::Black, PieceKind::Cannon) => '砲',
+        _ => get_piece_name(piece),
+    }
+}
+
+// 坐标走法转换为中文记谱, 同一纵线有多个同种棋子时:
+// 两个用前、后, 三个用前、中、后, 四五个用一二三四五从前往后数;
+// 多条纵线都有重叠的兵时, 用纵线代替兵种, 如 "前五平四"; 英文按WXF记谱输出
+pub fn board_move_chinese(board: &Board, mv: Move, locale: Locale) -> String {
+    if locale == Locale::English {
+        return board_move_wxf(board, mv);
+    }
+    let traditional = locale == Locale::Traditional;
+    let mut chinese = String::new();
+    let Some(piece) = board.get(mv.from) else { return chinese };
+    // 红方用中文数字, 黑方用阿拉伯数字
+    let verticals = get_verticals(piece.color);
+    let numeral = |n: usize| verticals[file_to_x(piece.color, n)];
+    let file = x_to_file(piece.color, mv.from.x());
+
+    match tandem(board, mv.from) {
+        None => {
+            chinese.push(piece_name(piece, locale));
+            chinese.push(numeral(file));
+        }
+        Some(tandem) => {
+            chinese.push(match (tandem.count, tandem.index) {
+                (2 | 3, 0) => '前',
+                (3, 1) => '中',
+                (2, 1) | (3, 2) if traditional => '後',
+                (2, 1) | (3, 2) => '后',
+                (_, index) => numeral(index + 1),
+            });
+            if tandem.multi_file {
+                chinese.push(numeral(file));
+            } else {
+                chinese.push(piece_name(piece, locale));
+            }
+        }
+    }
+
+    let (action, target) = action_of(piece, mv);
+    chinese.push(match action {
+        Action::Advance if traditional => '進',
+        Action::Advance => '进',
+        Action::Retreat => '退',
+        Action::Traverse => '平',
+    });
+    chinese.push(numeral(target));
+    chinese
+}
+
+fn parse_order(c: char) -> Option<Order> {
+    match c {
+        '前' => Some(Order::Front),
+        '中' => Some(Order::Middle),
+        '后' | '後' => Some(Order::Back),
+        _ => parse_number(c).filter(|&n| n <= 5).map(Order::Nth),
+    }
+}
+
+// 红方用中文数字, 黑方用阿拉伯数字, 解析时两者都接受
+fn parse_number(c: char) -> Option<usize> {
+    match c {
+        '1'..='9' => Some(c as usize - '0' as usize),
+        '１'..='９' => Some(c as usize - '１' as usize + 1),
+        _ => "一二三四五六七八九".chars().position(|n| n == c).map(|i| i + 1),
+    }
+}
+
+// 同时接受简体、繁体和常见的异体字
+fn parse_kind(c: char) -> Option<PieceKind> {
+    match c {
+        '帅' | '将' | '帥' | '將' => Some(PieceKind::King),
+        '仕' | '士' => Some(PieceKind::Advisor),
+        '相' | '象' => Some(PieceKind::Bishop),
+        '马' | '馬' | '傌' => Some(PieceKind::Knight),
+        '车' | '車' | '俥' => Some(PieceKind::Rook),
+        '炮' | '砲' | '包' => Some(PieceKind::Cannon),
+        '兵' | '卒' => Some(PieceKind::Pawn),
+        _ => None,
+    }
+}
+
+fn parse_action(c: char) -> Option<Action> {
+    match c {
+        '进' | '進' => Some(Action::Advance),
+        '退' => Some(Action::Retreat),
+        '平' => Some(Action::Traverse),
+        _ => None,
+    }
+}
+
+// 中文记谱转换为走法, 按当前行棋方解析, 如 "炮二平五"、"前车进一"、"马8进7"
+pub fn parse_chinese(board: &Board, text: &str) -> Result<Move, String> {
+    let invalid = || format!("无法识别的着法: {}", text);
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(4..=5).contains(&chars.len()) {
+        return Err(invalid());
+    }
+    let (prefix, tail) = chars.split_at(chars.len() - 2);
+    let action = parse_action(tail[0]).ok_or_else(invalid)?;
+    let target = parse_number(tail[1]).ok_or_else(invalid)?;
+
+    // 棋子、纵线、前后次序
+    let (kind, file, order) = match *prefix {
+        [p, f] if parse_kind(p).is_some() => (parse_kind(p), parse_number(f).map(Some), Some(None)),
+        [o, p] if parse_kind(p).is_some() => (parse_kind(p), Some(None), parse_order(o).map(Some)),
+        // 多条纵线都有重叠的兵, 用纵线代替兵种
+        [o, f] => (Some(PieceKind::Pawn), parse_number(f).map(Some), parse_order(o).map(Some)),
+        [o, p, f] => (parse_kind(p), parse_number(f).map(Some), parse_order(o).map(Some)),
+        _ => (None, None, None),
+    };
+    let (Some(kind), Some(file), Some(order)) = (kind, file, order) else {
+        return Err(invalid());
+    };
+    resolve(board, Mover { kind, file, order }, action, target, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::chess::movegen::legal_moves;
+
+    fn parse(fen: &str, text: &str) -> Result<String, String> {
+        parse_chinese(&fen.parse().unwrap(), text).map(|mv| mv.to_string())
+    }
+
+    #[test]
+    fn test_chinese() {
+        let fen = "2rakab2/9/1cn6/p3p3p/2b2n3/6R2/P3P1c1P/2N1C3C/4N4/2BAKAB2 w";
+        let mut board: Board = fen.parse().unwrap();
+        assert_eq!(board_move_chinese(&board, "g4g5".parse().unwrap(), Locale::Simplified), "车三进一");
+        for pv in ["g4g5", "b7b5", "g5g9", "c5e7", "g9g4", "f9e8", "i2i6", "c7d5"] {
+            let mv = pv.parse().unwrap();
+            let notice = board_move_chinese(&board, mv, Locale::Simplified);
+            board = board.make_move(mv);
+            println!("pv: {} => {}", pv, notice);
+        }
+    }
+
+    fn format(fen: &str, iccs: &str) -> String {
+        board_move_chinese(&fen.parse().unwrap(), iccs.parse().unwrap(), Locale::Simplified)
+    }
+
+    // 同一纵线重叠的各种情况
+    const TANDEM_CASES: [(&str, &[(&str, &str)]); 11] = [
+        ("4k4/9/9/9/9/9/7R1/9/9/3K3R1 w", &[("h3h5", "前车进二"), ("h0h1", "后车进一"), ("h3f3", "前车平四")]),
+        ("4k4/9/9/9/9/2N6/9/2N6/9/3K5 w", &[("c4d6", "前马进六"), ("c2b0", "后马退八")]),
+        ("3k5/9/9/9/9/9/1c7/1c7/9/4K4 b", &[("b2a2", "前炮平1"), ("b3b5", "后炮退2")]),
+        ("3k5/9/4P4/4P4/4P4/9/9/9/9/4K4 w", &[("e7e8", "前兵进一"), ("e6d6", "中兵平六"), ("e5f5", "后兵平四")]),
+        ("3k5/4P4/4P4/4P4/4P4/9/9/9/9/4K4 w", &[("e8f8", "一兵平四"), ("e7d7", "二兵平六"), ("e5f5", "四兵平四")]),
+        ("2P1k4/2P6/2P6/2P6/2P6/9/9/9/9/3K5 w", &[("c9b9", "一兵平八"), ("c7d7", "三兵平六"), ("c5b5", "五兵平八")]),
+        ("3k5/9/2P1P4/2P1P4/9/9/9/9/9/4K4 w", &[("e7f7", "前五平四"), ("e6d6", "后五平六"), ("c6b6", "后七平八")]),
+        ("3k5/9/2P1P4/2P1P4/4P4/9/9/9/9/4K4 w", &[("e6d6", "中五平六"), ("e5f5", "后五平四"), ("c7c8", "前七进一")]),
+        ("4k4/9/9/9/9/4p4/4p4/4p4/9/3K5 b", &[("e2d2", "前卒平4"), ("e3d3", "中卒平4"), ("e4f4", "后卒平6")]),
+        ("4k4/9/9/9/9/2p1p4/2p1p4/9/9/3K5 b", &[("c3b3", "前3平2"), ("e4f4", "后5平6"), ("e3e2", "前5进1")]),
+        ("3k5/9/9/9/9/9/9/3A5/9/3AK4 w", &[("d2e1", "仕六退五"), ("d0e1", "仕六进五")]),
+    ];
+
+    #[test]
+    fn test_board_move_chinese() {
+        let start = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        assert_eq!(format(start, "h2e2"), "炮二平五");
+        assert_eq!(format(start, "h0g2"), "马二进三");
+        assert_eq!(format(start, "b2b9"), "炮八进七");
+        assert_eq!(format(start, "e0e1"), "帅五进一");
+        let black = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b";
+        assert_eq!(format(black, "h9g7"), "马8进7");
+        assert_eq!(format(black, "i9i7"), "车9进2");
+        assert_eq!(format(black, "e9e8"), "将5进1");
+
+        for (fen, cases) in TANDEM_CASES {
+            for (iccs, expected) in cases {
+                assert_eq!(format(fen, iccs), *expected, "{} {}", fen, iccs);
+            }
+        }
+    }
+
+    // 每个局面的所有合法走法, 记谱互不相同且能解析回原走法
+    #[test]
+    fn test_tandem_exhaustive() {
+        for (fen, _) in TANDEM_CASES {
+            let board: Board = fen.parse().unwrap();
+            let moves = legal_moves(&board);
+            for locale in [Locale::Simplified, Locale::Traditional] {
+                let texts: HashSet<String> = moves.iter().map(|&mv| board_move_chinese(&board, mv, locale)).collect();
+                assert_eq!(texts.len(), moves.len(), "{}", fen);
+                for &mv in &moves {
+                    let text = board_move_chinese(&board, mv, locale);
+                    assert_eq!(parse_chinese(&board, &text), Ok(mv), "{} {}", fen, text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_locale() {
+        let cases = [
+            ("rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w", "i0i1", ["车一进一", "俥一進一", "R1+1"]),
+            ("rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w", "h0g2", ["马二进三", "傌二進三", "H2+3"]),
+            ("rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w", "e0e1", ["帅五进一", "帥五進一", "K5+1"]),
+            ("rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b", "h7e7", ["炮8平5", "砲8平5", "C8=5"]),
+            ("rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b", "b9c7", ["马2进3", "馬2進3", "H2+3"]),
+            ("4k4/9/9/9/9/9/7R1/9/9/3K3R1 w", "h0h1", ["后车进一", "後俥進一", "-R+1"]),
+        ];
+        for (fen, iccs, expected) in cases {
+            let board: Board = fen.parse().unwrap();
+            let mv = iccs.parse().unwrap();
+            for (locale, text) in [Locale::Simplified, Locale::Traditional, Locale::English].into_iter().zip(expected) {
+                assert_eq!(board_move_chinese(&board, mv, locale), text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_chinese() {
+        let start = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        assert_eq!(parse(start, "炮二平五"), Ok("h2e2".to_string()));
+        assert_eq!(parse(start, "炮2平5"), Ok("h2e2".to_string()));
+        assert_eq!(parse(start, " 马 二 进 三 "), Ok("h0g2".to_string()));
+        assert_eq!(parse(start, "相三进五"), Ok("g0e2".to_string()));
+        assert_eq!(parse(start, "仕四进五"), Ok("f0e1".to_string()));
+        assert_eq!(parse(start, "帅五进一"), Ok("e0e1".to_string()));
+        assert_eq!(parse(start, "兵七进一"), Ok("c3c4".to_string()));
+        assert_eq!(parse(start, "炮八进七"), Ok("b2b9".to_string()));
+        assert_eq!(parse(start, "車一進一"), Ok("i0i1".to_string()));
+
+        let black = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b";
+        assert_eq!(parse(black, "马8进7"), Ok("h9g7".to_string()));
+        assert_eq!(parse(black, "马八进七"), Ok("h9g7".to_string()));
+        assert_eq!(parse(black, "炮8平5"), Ok("h7e7".to_string()));
+        assert_eq!(parse(black, "卒3进1"), Ok("c6c5".to_string()));
+        assert_eq!(parse(black, "象3进5"), Ok("c9e7".to_string()));
+    }
+
+    #[test]
+    fn test_parse_tandem() {
+        // 二路双车
+        let rooks = "4k4/9/9/9/9/9/7R1/9/9/3K3R1 w";
+        assert_eq!(parse(rooks, "前车平四"), Ok("h3f3".to_string()));
+        assert_eq!(parse(rooks, "后车进一"), Ok("h0h1".to_string()));
+        assert!(parse(rooks, "车二进一").unwrap_err().contains("歧义"));
+        // 后车被前车挡住, 只有前车能进三
+        assert_eq!(parse(rooks, "车二进三"), Ok("h3h6".to_string()));
+
+        // 三个兵在同一纵线
+        let pawns = "4k4/9/4P4/4P4/4P4/9/9/9/9/3K5 w";
+        assert_eq!(parse(pawns, "前兵平四"), Ok("e7f7".to_string()));
+        assert_eq!(parse(pawns, "中兵平六"), Ok("e6d6".to_string()));
+        assert_eq!(parse(pawns, "后兵平四"), Ok("e5f5".to_string()));
+        assert_eq!(parse(pawns, "二兵平四"), Ok("e6f6".to_string()));
+
+        // 两条纵线都有重叠的兵
+        let files = "4k4/9/2P1P4/2P1P4/9/9/9/9/9/3K5 w";
+        assert!(parse(files, "前兵平四").unwrap_err().contains("歧义"));
+        assert_eq!(parse(files, "前兵五平四"), Ok("e7f7".to_string()));
+        assert_eq!(parse(files, "后兵七平八"), Ok("c6b6".to_string()));
+        assert_eq!(parse(files, "三兵平八"), Ok("c7b7".to_string()));
+
+        // 黑方前后以黑方视角为准
+        let black = "4k4/9/9/9/9/9/1c7/1c7/9/3K5 b";
+        assert_eq!(parse(black, "前炮平1"), Ok("b2a2".to_string()));
+        assert_eq!(parse(black, "后炮平1"), Ok("b3a3".to_string()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let start = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        for text in ["", "炮二平", "炮二平五进", "炮二跳五", "炮零平五", "二炮平五", "前炮平五"]
+        {
+            assert!(parse(start, text).is_err(), "{}", text);
+        }
+        assert!(parse(start, "车一进四").unwrap_err().contains("不合法"));
+        assert!(parse(start, "马二进四").unwrap_err().contains("不合法"));
+        assert!(parse(start, "相三平五").unwrap_err().contains("不合法"));
+        assert!(parse(start, "炮五平四").unwrap_err().contains("找不到"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for fen in [
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w",
+            "2rakab2/9/1cn6/p3p3p/2b2n3/6R2/P3P1c1P/2N1C3C/4N4/2BAKAB2 w",
+            "2rakab2/9/1cn6/p3p3p/2b2n3/6R2/P3P1c1P/2N1C3C/4N4/2BAKAB2 b",
+            "r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w",
+            "1cbak4/9/n2a5/2p1p3p/5cp2/2n2N3/6PCP/3AB4/2C6/3A1K1N1 w",
+            "4k4/9/9/9/9/9/7R1/9/9/3K3R1 w",
+        ] {
+            let board: Board = fen.parse().unwrap();
+            for mv in legal_moves(&board) {
+                let text = board_move_chinese(&board, mv, Locale::Simplified);
+                assert_eq!(parse_chinese(&board, &text), Ok(mv), "{} {}", fen, text);
+            }
+        }
+    }
+}
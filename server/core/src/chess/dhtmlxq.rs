@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+
+use super::game::Game;
+use super::game::GameResult;
+use super::game::Node;
+use super::game::MAX_PLIES;
+use super::game::PIECE_ORDER;
+use super::movegen::is_legal_move;
+use super::Board;
+use super::Color;
+use super::Move;
+use super::Piece;
+use super::Square;
+use super::BOARD_MAP;
+
+// 不在棋盘上的棋子
+const CAPTURED: &str = "99";
+
+// 两位数字坐标 列+行, 行从黑方底线开始, 与BOARD_MAP的下标一致
+fn square(pair: &[u8]) -> Result<Square, String> {
+    match pair {
+        &[x @ b'0'..=b'8', y @ b'0'..=b'9'] => BOARD_MAP[(y - b'0') as usize][(x - b'0') as usize].parse(),
+        _ => Err(format!("DhtmlXQ坐标无效: {}", String::from_utf8_lossy(pair))),
+    }
+}
+
+fn coord(sq: Square) -> String { format!("{}{}", sq.x(), sq.y()) }
+
+// 每步着法为4位数字: 起点 + 终点
+fn parse_moves(text: &str) -> Result<Vec<Move>, String> {
+    let digits: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(4) {
+        return Err(format!("DhtmlXQ着法长度无效: {}", text));
+    }
+    digits.chunks(4).map(|chunk| Ok(Move::new(square(&chunk[..2])?, square(&chunk[2..])?))).collect()
+}
+
+// 提取 [DhtmlXQ_名称]内容[/DhtmlXQ_名称] 形式的全部标签
+fn tags(text: &str) -> BTreeMap<String, String> {
+    let mut tags = BTreeMap::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[DhtmlXQ_") {
+        rest = &rest[start + "[DhtmlXQ_".len()..];
+        let Some(end) = rest.find(']') else { break };
+        let name = &rest[..end];
+        rest = &rest[end + 1..];
+        let close = format!("[/DhtmlXQ_{}]", name);
+        if let Some(end) = rest.find(&close) {
+            tags.insert(name.to_string(), rest[..end].to_string());
+            rest = &rest[end + close.len()..];
+        }
+    }
+    tags
+}
+
+// 注释中的换行写作 "||"
+fn comment_key(branch: usize, step: usize) -> String {
+    match branch {
+        0 => format!("comment{}", step),
+        _ => format!("comment{}_{}", branch, step),
+    }
+}
+
+// 一条着法分支: 从父分支的第step步开始, 替换父分支的该步着法
+struct Branch {
+    parent: usize,
+    step: usize,
+    moves: Vec<Move>,
+}
+
+struct Parser {
+    tags: BTreeMap<String, String>,
+    branches: BTreeMap<usize, Branch>,
+}
+
+impl Parser {
+    fn comment(&self, branch: usize, step: usize) -> String {
+        self.tags.get(&comment_key(branch, step)).map(|text| text.replace("||", "\n")).unwrap_or_default()
+    }
+
+    // 分支中第index个着法(全局第step步)所在的一组同级着法, 本分支的着法在前, 从这一步分出的变着在后
+    fn siblings(&self, id: usize, index: usize, board: &Board, step: usize) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        if let Some(&mv) = self.branches[&id].moves.get(index) {
+            if step > MAX_PLIES {
+                return Err(format!("DhtmlXQ棋谱超过{}步", MAX_PLIES));
+            }
+            if !is_legal_move(board, mv) {
+                return Err(format!("DhtmlXQ着法不合法: {}", mv));
+            }
+            let children = self.siblings(id, index + 1, &board.make_move(mv), step + 1)?;
+            nodes.push(Node { mv: Some(mv), comment: self.comment(id, step), children });
+        }
+        // 子分支的编号总是大于父分支, 避免循环引用
+        for (&var, branch) in self.branches.range(id + 1..) {
+            if branch.parent == id && branch.step == step {
+                nodes.extend(self.siblings(var, 0, board, step)?);
+            }
+        }
+        Ok(nodes)
+    }
+}
+
+// 解析论坛分享的DhtmlXQ棋谱
+pub fn parse_dhtmlxq(text: &str) -> Result<Game, String> {
+    let tags = tags(text);
+    if !tags.contains_key("binit") && !tags.contains_key("movelist") {
+        return Err("不是DhtmlXQ棋谱".to_string());
+    }
+
+    let mut game = Game::new(Board::startpos());
+    if let Some(binit) = tags.get("binit") {
+        let digits: Vec<u8> = binit.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+        if digits.len() != 64 {
+            return Err(format!("DhtmlXQ_binit应有64位数字, 实际为{}位", digits.len()));
+        }
+        game.start = Board::default();
+        for (i, pair) in digits.chunks(2).enumerate() {
+            if pair != CAPTURED.as_bytes() {
+                game.start.set(square(pair)?, Piece::from_char(PIECE_ORDER[i] as char));
+            }
+        }
+        game.start.check_counts()?;
+    }
+    let tag = |name: &str| tags.get(name).cloned().unwrap_or_default();
+    game.title = tag("title");
+    game.event = tag("event");
+    game.date = tag("date");
+    game.site = tag("place");
+    game.red = tag("red");
+    game.black = tag("black");
+    game.result = match tag("result").as_str() {
+        "红胜" => GameResult::RedWin,
+        "黑胜" => GameResult::BlackWin,
+        "和棋" | "和局" => GameResult::Draw,
+        _ => GameResult::Unknown,
+    };
+
+    // 主线为0号分支, 变着为 move_父分支_起始步_分支编号
+    let mut branches = BTreeMap::new();
+    let moves = parse_moves(tags.get("movelist").map_or("", |text| text.as_str()))?;
+    branches.insert(0, Branch { parent: 0, step: 1, moves });
+    for (name, value) in &tags {
+        let Some(numbers) = name.strip_prefix("move_") else { continue };
+        let numbers: Vec<usize> = numbers.split('_').filter_map(|n| n.parse().ok()).collect();
+        match numbers[..] {
+            [parent, step, id] if id > 0 && step > 0 => {
+                branches.insert(id, Branch { parent, step, moves: parse_moves(value)? });
+            }
+            _ => return Err(format!("DhtmlXQ变着标签无效: {}", name)),
+        }
+    }
+
+    // 起始局面不记录行棋方, 由主线第一步决定, 之后双方轮流走
+    game.start.turn =
+        branches[&0].moves.first().and_then(|mv| game.start.get(mv.from)).map_or(Color::Red, |piece| piece.color);
+    let parser = Parser { tags, branches };
+    game.root.comment = parser.comment(0, 0);
+    game.root.children = parser.siblings(0, 0, &game.start, 1)?;
+    Ok(game)
+}
+
+struct Emitter {
+    // (父分支, 起始步, 着法)
+    branches: Vec<(usize, usize, String)>,
+    comments: Vec<(String, String)>,
+}
+
+impl Emitter {
+    fn node(&mut self, node: &Node, id: usize, step: usize) -> Result<(), String> {
+        let mv = node.mv.ok_or("棋谱节点缺少着法")?;
+        self.branches[id].2.push_str(&format!("{}{}", coord(mv.from), coord(mv.to)));
+        if !node.comment.is_empty() {
+            self.comments.push((comment_key(id, step), node.comment.replace('\n', "||")));
+        }
+        self.siblings(&node.children, id, step + 1)
+    }
+
+    // 第一个着法沿用当前分支, 其余的变着各开一个新分支
+    fn siblings(&mut self, nodes: &[Node], id: usize, step: usize) -> Result<(), String> {
+        let Some((first, rest)) = nodes.split_first() else { return Ok(()) };
+        self.node(first, id, step)?;
+        for node in rest {
+            self.branches.push((id, step, String::new()));
+            self.node(node, self.branches.len() - 1, step)?;
+        }
+        Ok(())
+    }
+}
+
+// 棋谱转换为DhtmlXQ文本, 可以直接贴到论坛
+pub fn to_dhtmlxq(game: &Game) -> Result<String, String> {
+    // 与常见的写法一致, 双方棋子各自从己方的右侧开始编号
+    let mut pieces: Vec<(Square, Piece)> = game.start.pieces().collect();
+    pieces.sort_by_key(|(sq, piece)| match piece.color {
+        Color::Red => (8 - sq.x(), 9 - sq.y()),
+        Color::Black => (sq.x(), sq.y()),
+    });
+    let mut slots = [None; 32];
+    for (sq, piece) in pieces {
+        let slot = (0..32)
+            .find(|&i| PIECE_ORDER[i] as char == piece.to_char() && slots[i].is_none())
+            .ok_or_else(|| format!("棋子数量超出DhtmlXQ的限制: {}", piece.to_char()))?;
+        slots[slot] = Some(sq);
+    }
+    let binit: String = slots.iter().map(|sq| sq.map_or(CAPTURED.to_string(), coord)).collect();
+
+    let mut emitter = Emitter { branches: vec![(0, 1, String::new())], comments: Vec::new() };
+    if !game.root.comment.is_empty() {
+        emitter.comments.push((comment_key(0, 0), game.root.comment.replace('\n', "||")));
+    }
+    emitter.siblings(&game.root.children, 0, 1)?;
+
+    let result = match game.result {
+        GameResult::RedWin => "红胜",
+        GameResult::BlackWin => "黑胜",
+        GameResult::Draw => "和棋",
+        GameResult::Unknown => "未知",
+    };
+    let mut tags = vec![("ver".to_string(), "www_dpxq_com".to_string())];
+    for (name, value) in [
+        ("title", &game.title),
+        ("event", &game.event),
+        ("date", &game.date),
+        ("place", &game.site),
+        ("red", &game.red),
+        ("black", &game.black),
+    ] {
+        if !value.is_empty() {
+            tags.push((name.to_string(), value.clone()));
+        }
+    }
+    tags.push(("result".to_string(), result.to_string()));
+    tags.push(("binit".to_string(), binit));
+    for (id, (parent, step, moves)) in emitter.branches.into_iter().enumerate() {
+        match id {
+            0 => tags.push(("movelist".to_string(), moves)),
+            _ => tags.push((format!("move_{}_{}_{}", parent, step, id), moves)),
+        }
+    }
+    tags.extend(emitter.comments);
+
+    let mut text = String::from("[DhtmlXQ]\n");
+    for (name, value) in tags {
+        text.push_str(&format!("[DhtmlXQ_{0}]{1}[/DhtmlXQ_{0}]\n", name, value));
+    }
+    text.push_str("[/DhtmlXQ]\n");
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_BINIT: &str = "8979695949392919097717866646260600102030405060708012720323436383";
+
+    fn mv(text: &str) -> Move { text.parse().unwrap() }
+
+    fn node(text: &str, comment: &str, children: Vec<Node>) -> Node {
+        Node { mv: Some(mv(text)), comment: comment.to_string(), children }
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = "论坛帖子正文
+[DhtmlXQ]
+[DhtmlXQ_ver]www_dpxq_com[/DhtmlXQ_ver]
+[DhtmlXQ_title]中炮对屏风马[/DhtmlXQ_title]
+[DhtmlXQ_red]胡荣华[/DhtmlXQ_red]
+[DhtmlXQ_result]红胜[/DhtmlXQ_result]
+[DhtmlXQ_binit]8979695949392919097717866646260600102030405060708012720323436383[/DhtmlXQ_binit]
+[DhtmlXQ_movelist]77477062[/DhtmlXQ_movelist]
+[DhtmlXQ_move_0_2_1]1022[/DhtmlXQ_move_0_2_1]
+[DhtmlXQ_comment0]开局[/DhtmlXQ_comment0]
+[DhtmlXQ_comment1]中炮[/DhtmlXQ_comment1]
+[DhtmlXQ_comment1_2]变着||换行[/DhtmlXQ_comment1_2]
+[/DhtmlXQ]";
+        let game = parse_dhtmlxq(text).unwrap();
+        assert_eq!(game.start, Board::startpos());
+        assert_eq!((game.title.as_str(), game.red.as_str()), ("中炮对屏风马", "胡荣华"));
+        assert_eq!(game.result, GameResult::RedWin);
+        assert_eq!(game.root.comment, "开局");
+        assert_eq!(game.main_line(), vec![mv("h2e2"), mv("h9g7")]);
+        let first = &game.root.children[0];
+        assert_eq!(first.comment, "中炮");
+        assert_eq!(first.children[1], node("b9c7", "变着\n换行", vec![]));
+
+        // 没有binit时为开局局面
+        let game = parse_dhtmlxq("[DhtmlXQ_movelist]7747[/DhtmlXQ_movelist]").unwrap();
+        assert_eq!(game.start, Board::startpos());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut game = Game::new(Board::startpos());
+        game.title = "测试对局".to_string();
+        game.site = "上海".to_string();
+        game.result = GameResult::Draw;
+        game.root.comment = "第一行\n第二行".to_string();
+        game.root.children = vec![
+            node(
+                "h2e2",
+                "当头炮",
+                vec![
+                    node(
+                        "h9g7",
+                        "",
+                        vec![node("h0g2", "", vec![node("i9h9", "出车", vec![])]), node("b0c2", "变着", vec![])],
+                    ),
+                    node("b7e7", "顺炮", vec![node("h0g2", "", vec![]), node("b0c2", "", vec![])]),
+                ],
+            ),
+            node("c3c4", "仙人指路", vec![]),
+        ];
+        let text = to_dhtmlxq(&game).unwrap();
+        assert!(text.contains(&format!("[DhtmlXQ_binit]{}[/DhtmlXQ_binit]", START_BINIT)));
+        assert!(text.contains("[DhtmlXQ_movelist]7747706279678070[/DhtmlXQ_movelist]"));
+        assert!(text.contains("[DhtmlXQ_comment0]第一行||第二行[/DhtmlXQ_comment0]"));
+        assert_eq!(parse_dhtmlxq(&text).unwrap(), game);
+
+        // 黑方先行, 有被吃掉的棋子
+        let mut game = Game::new("3k5/9/9/9/9/9/9/9/4r4/3K5 b".parse().unwrap());
+        game.root.children = vec![node("e1d1", "", vec![node("d0e0", "", vec![])])];
+        let text = to_dhtmlxq(&game).unwrap();
+        assert!(text.contains(
+            "[DhtmlXQ_binit]9999999939999999999999999999999948999999309999999999999999999999[/DhtmlXQ_binit]"
+        ));
+        assert_eq!(parse_dhtmlxq(&text).unwrap(), game);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse_dhtmlxq("[DhtmlXQ][/DhtmlXQ]").unwrap_err().contains("不是DhtmlXQ"));
+        assert!(parse_dhtmlxq("[DhtmlXQ_binit]8979[/DhtmlXQ_binit]").unwrap_err().contains("64"));
+        assert!(parse_dhtmlxq("[DhtmlXQ_movelist]774[/DhtmlXQ_movelist]").is_err());
+        assert!(parse_dhtmlxq("[DhtmlXQ_movelist]7797[/DhtmlXQ_movelist]").unwrap_err().contains("坐标"));
+        assert!(parse_dhtmlxq("[DhtmlXQ_movelist]7740[/DhtmlXQ_movelist]").unwrap_err().contains("不合法"));
+        let text = "[DhtmlXQ_movelist]7747[/DhtmlXQ_movelist][DhtmlXQ_move_0_x_1]1022[/DhtmlXQ_move_0_x_1]";
+        assert!(parse_dhtmlxq(text).unwrap_err().contains("变着"));
+    }
+
+    // 双方必须轮流走, 只有主线第一步可以决定行棋方
+    #[test]
+    fn test_turn() {
+        // 炮二平五后红方再走马二进三
+        assert!(parse_dhtmlxq("[DhtmlXQ_movelist]77477062[/DhtmlXQ_movelist]").is_ok());
+        assert!(parse_dhtmlxq("[DhtmlXQ_movelist]77477967[/DhtmlXQ_movelist]").unwrap_err().contains("不合法"));
+        // 第一步的变着也要由同一方走
+        let text = "[DhtmlXQ_movelist]7747[/DhtmlXQ_movelist][DhtmlXQ_move_0_1_1]7062[/DhtmlXQ_move_0_1_1]";
+        assert!(parse_dhtmlxq(text).unwrap_err().contains("不合法"));
+    }
+
+    #[test]
+    fn test_max_plies() {
+        // 马来回跳动
+        let text = |plies: usize| {
+            let moves: String = (0..plies).map(|i| ["7967", "7062", "6779", "6270"][i % 4]).collect();
+            format!("[DhtmlXQ_movelist]{}[/DhtmlXQ_movelist]", moves)
+        };
+        assert_eq!(parse_dhtmlxq(&text(MAX_PLIES)).unwrap().main_line().len(), MAX_PLIES);
+        assert!(parse_dhtmlxq(&text(MAX_PLIES + 1)).unwrap_err().contains("超过"));
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::Board;
+use super::Color;
+use super::Piece;
+use super::Square;
+
+// 棋盘转换FEN逻辑, 输出完整的 "w - - 0 1" 尾部
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..10 {
+            let mut empty = 0;
+            for x in 0..9 {
+                match self.get(Square::new(x, y)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            write!(f, "{}", empty)?;
+                            empty = 0;
+                        }
+                        write!(f, "{}", piece.to_char())?;
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                write!(f, "{}", empty)?;
+            }
+            if y < 9 {
+                f.write_str("/")?;
+            }
+        }
+        write!(f, " {} - - {} {}", self.turn.to_char(), self.halfmove, self.fullmove)
+    }
+}
+
+// FEN转换为棋盘, 行棋方及之后的字段可以省略
+impl FromStr for Board {
+    type Err = String;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or("FEN为空")?;
+
+        let mut board = Board::default();
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 10 {
+            return Err(format!("FEN应有10行, 实际为{}行: {}", ranks.len(), placement));
+        }
+        for (y, rank) in ranks.iter().enumerate() {
+            let mut x = 0;
+            for c in rank.chars() {
+                match c {
+                    '1'..='9' => x += c.to_digit(10).unwrap() as usize,
+                    _ => {
+                        let piece = Piece::from_char(c).ok_or_else(|| format!("第{}行有未知的棋子: {}", y + 1, c))?;
+                        if x < 9 {
+                            board.set(Square::new(x, y), Some(piece));
+                        }
+                        x += 1;
+                    }
+                }
+            }
+            if x != 9 {
+                return Err(format!("第{}行应有9列, 实际为{}列: {}", y + 1, x, rank));
+            }
+        }
+        board.check_counts()?;
+
+        board.turn = match fields.next() {
+            None | Some("w") | Some("r") => Color::Red,
+            Some("b") => Color::Black,
+            Some(side) => return Err(format!("未知的行棋方: {}", side)),
+        };
+        // 王车易位和吃过路兵两个字段在中国象棋中固定为 "-"
+        for field in fields.by_ref().take(2) {
+            if field != "-" {
+                return Err(format!("无效的FEN字段: {}", field));
+            }
+        }
+        if let Some(halfmove) = fields.next() {
+            board.halfmove = halfmove.parse().map_err(|_| format!("无效的半回合数: {}", halfmove))?;
+        }
+        if let Some(fullmove) = fields.next() {
+            board.fullmove = match fullmove.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(format!("无效的回合数: {}", fullmove)),
+            };
+        }
+        if let Some(extra) = fields.next() {
+            return Err(format!("多余的FEN字段: {}", extra));
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 现有测试中用到的局面
+    const FENS: [&str; 8] = [
+        "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1",
+        "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b - - 1 1",
+        "2rakab2/9/1cn6/p3p3p/2b2n3/6R2/P3P1c1P/2N1C3C/4N4/2BAKAB2 w - - 0 1",
+        "r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w - - 0 1",
+        "1cbak4/9/n2a5/2p1p3p/5cp2/2n2N3/6PCP/3AB4/2C6/3A1K1N1 w - - 0 1",
+        "CRN1k1b2/3ca4/4ba3/9/2nr5/9/9/4B4/4A4/4KA3 w - - 0 1",
+        "3k5/4R4/3R5/9/9/9/9/9/9/4K4 b - - 12 40",
+        "3k5/2P6/9/9/9/9/9/9/9/4K4 b - - 0 60",
+    ];
+
+    #[test]
+    fn test_round_trip() {
+        for fen in FENS {
+            let board: Board = fen.parse().unwrap();
+            assert_eq!(board.to_string(), fen);
+            assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+        }
+    }
+
+    #[test]
+    fn test_short_fen() {
+        let board: Board = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR".parse().unwrap();
+        assert_eq!(board, Board::startpos());
+        let board: Board = "3k5/4R4/3R5/9/9/9/9/9/9/4K4 b".parse().unwrap();
+        assert_eq!(board.turn, Color::Black);
+        assert_eq!(board.to_string(), "3k5/4R4/3R5/9/9/9/9/9/9/4K4 b - - 0 1");
+    }
+
+    #[test]
+    fn test_invalid_fen() {
+        for fen in [
+            "",
+            // 行数不对
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/RNBAKABNR w",
+            // 列数过多或过少
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C2/9/RNBAKABNR w",
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C/9/RNBAKABNR w",
+            "rnbakabnrr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w",
+            // 未知棋子
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5X1/9/RNBAKABNR w",
+            // 棋子数量不合法
+            "rnbakaanr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w",
+            "4k4/9/9/9/9/9/9/9/9/9 w",
+            // 尾部字段不合法
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR x",
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w KQ - 0 1",
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - a 1",
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 0",
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1 1",
+        ] {
+            assert!(fen.parse::<Board>().is_err(), "{}", fen);
+        }
+        let err = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5X1/9/RNBAKABNR w".parse::<Board>().unwrap_err();
+        assert_eq!(err, "第8行有未知的棋子: X");
+        let err = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C/9/RNBAKABNR w".parse::<Board>().unwrap_err();
+        assert_eq!(err, "第8行应有9列, 实际为8列: 1C5C");
+    }
+}
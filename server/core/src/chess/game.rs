@@ -0,0 +1,56 @@
+use std::iter::successors;
+
+use super::Board;
+use super::Move;
+
+// XQF、DhtmlXQ等棋谱格式中32个棋子的固定顺序
+pub const PIECE_ORDER: &[u8; 32] = b"RNBAKABNRCCPPPPPrnbakabnrccppppp";
+
+// 读取棋谱时允许的最大步数, 棋谱树按步递归, 防止构造的文件耗尽栈空间
+pub const MAX_PLIES: usize = 1000;
+
+// 对局结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameResult {
+    #[default]
+    Unknown,
+    RedWin,
+    BlackWin,
+    Draw,
+}
+
+// 棋谱树的节点, 第一个子节点为主线, 其余为变着
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Node {
+    pub mv: Option<Move>, // 根节点没有着法
+    pub comment: String,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(mv: Move) -> Self { Self { mv: Some(mv), ..Default::default() } }
+}
+
+// 带注释和变着的棋谱
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Game {
+    pub title: String,     // 标题
+    pub event: String,     // 赛事
+    pub date: String,      // 日期
+    pub site: String,      // 地点
+    pub red: String,       // 红方
+    pub black: String,     // 黑方
+    pub annotator: String, // 评注人
+    pub result: GameResult,
+    pub start: Board, // 起始局面, 行棋方为第一步的走子方
+    pub root: Node,
+}
+
+impl Game {
+    pub fn new(start: Board) -> Self { Self { start, ..Default::default() } }
+
+    // 主线着法
+    pub fn main_line(&self) -> Vec<Move> {
+        successors(self.root.children.first(), |node| node.children.first()).filter_map(|node| node.mv).collect()
+    }
+}
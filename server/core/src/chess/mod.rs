@@ -0,0 +1,304 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+mod board;
+mod chinese;
+pub mod dhtmlxq;
+mod fen;
+pub mod game;
+pub mod movegen;
+mod notation;
+pub mod perft;
+pub mod repetition;
+pub mod wxf;
+pub mod xqf;
+mod zobrist;
+
+pub use board::Board;
+pub use board::Color;
+pub use board::Move;
+pub use board::Piece;
+pub use board::PieceKind;
+pub use board::Square;
+pub use chinese::board_move_chinese;
+pub use chinese::parse_chinese;
+
+pub const BOARD_MAP: [[&str; 9]; 10] = [
+    ["a9", "b9", "c9", "d9", "e9", "f9", "g9", "h9", "i9"],
+    ["a8", "b8", "c8", "d8", "e8", "f8", "g8", "h8", "i8"],
+    ["a7", "b7", "c7", "d7", "e7", "f7", "g7", "h7", "i7"],
+    ["a6", "b6", "c6", "d6", "e6", "f6", "g6", "h6", "i6"],
+    ["a5", "b5", "c5", "d5", "e5", "f5", "g5", "h5", "i5"],
+    ["a4", "b4", "c4", "d4", "e4", "f4", "g4", "h4", "i4"],
+    ["a3", "b3", "c3", "d3", "e3", "f3", "g3", "h3", "i3"],
+    ["a2", "b2", "c2", "d2", "e2", "f2", "g2", "h2", "i2"],
+    ["a1", "b1", "c1", "d1", "e1", "f1", "g1", "h1", "i1"],
+    ["a0", "b0", "c0", "d0", "e0", "f0", "g0", "h0", "i0"],
+];
+
+#[derive(Debug, Serialize)]
+pub struct Position {
+    piece: char,
+    pos: String,
+}
+
+#[derive(Debug)]
+pub enum BoardChangeState {
+    // 变化了一个棋子
+    One,
+    // 正常一步棋移动
+    Move(Changed),
+    // 未知多个变化
+    Unknown,
+}
+
+// 行棋方的局面状态
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum GameStatus {
+    // 正常
+    Ongoing,
+    // 被将军
+    Check,
+    // 被将死
+    Checkmate,
+    // 困毙
+    Stalemate,
+}
+
+// 对局结束信息
+#[derive(Debug, Clone, Serialize)]
+pub struct GameOver {
+    pub winner: Color,
+    pub reason: GameStatus,
+}
+
+// 识别出的我方阵营
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize)]
+pub enum Camp {
+    #[default]
+    None,
+    Red,
+    Black,
+}
+
+impl Camp {
+    pub fn is_black(&self) -> bool { Camp::Black.eq(self) }
+
+    // 未识别出阵营时按红方处理
+    pub fn color(&self) -> Color {
+        match self {
+            Camp::Black => Color::Black,
+            _ => Color::Red,
+        }
+    }
+}
+
+// 走法记谱的显示语言
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy, Serialize, Deserialize)]
+pub enum Locale {
+    // 简体中文: 炮二平五
+    #[default]
+    Simplified,
+    // 繁体中文: 炮二平五 / 俥一進一
+    Traditional,
+    // 英文字母, 与WXF记谱相同: C2=5
+    English,
+}
+
+const BLACK_VERTICALS: [char; 9] = ['1', '2', '3', '4', '5', '6', '7', '8', '9'];
+const RED_VERTICALS: [char; 9] = ['九', '八', '七', '六', '五', '四', '三', '二', '一'];
+
+pub fn get_verticals(color: Color) -> [char; 9] {
+    match color {
+        Color::Red => RED_VERTICALS,
+        Color::Black => BLACK_VERTICALS,
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Changed {
+    pub piece: Piece,
+    pub camp: Color,
+    pub from: Square,
+    pub to: Square,
+}
+
+impl Changed {
+    pub fn new(board: &Board, mv: Move) -> Option<Self> {
+        let piece = board.get(mv.from)?;
+        Some(Self { piece, camp: piece.color, from: mv.from, to: mv.to })
+    }
+
+    pub fn mv(&self) -> Move { Move::new(self.from, self.to) }
+}
+
+impl Board {
+    // 对比棋盘, 返回发生的变化
+    pub fn diff(&self, board: &Board) -> BoardChangeState {
+        let mut from = None;
+        let mut to = None;
+        let mut count = 0;
+        for sq in Square::all() {
+            if self.get(sq) != board.get(sq) {
+                count += 1;
+                match board.get(sq) {
+                    None => from = self.get(sq).map(|piece| (sq, piece)),
+                    Some(_) => to = Some(sq),
+                }
+            }
+        }
+
+        match (count, from, to) {
+            (2, Some((from, piece)), Some(to)) => {
+                BoardChangeState::Move(Changed { piece, camp: piece.color, from, to })
+            }
+            (1 | 2, _, _) => BoardChangeState::One,
+            _ => BoardChangeState::Unknown,
+        }
+    }
+
+    // 黑方视角的棋盘旋转为红方在下
+    pub fn fix(&mut self, camp: &Camp) {
+        if Camp::Black.eq(camp) {
+            self.rotate();
+        }
+    }
+
+    // 棋盘转换为坐标模式
+    pub fn map(&self) -> Vec<Position> {
+        Square::all()
+            .map(|sq| Position { piece: self.get(sq).map_or(' ', Piece::to_char), pos: sq.to_string() })
+            .collect()
+    }
+}
+
+// 引擎思考翻译为中文和WXF记谱, 遇到不合法的着法时停止
+pub fn translate_pv(board: &Board, pvs: &[String], locale: Locale) -> (Vec<String>, Vec<String>) {
+    let mut board = *board;
+    let (mut moves, mut wxf) = (Vec::new(), Vec::new());
+    for pv in pvs {
+        let Some(mv) = pv.parse::<Move>().ok().filter(|&mv| movegen::is_legal_move(&board, mv)) else { break };
+        moves.push(board_move_chinese(&board, mv, locale));
+        wxf.push(wxf::board_move_wxf(&board, mv));
+        board = board.make_move(mv);
+    }
+    (moves, wxf)
+}
+
+// 解析用户输入的着法, 支持ICCS(如 h2e2、H2-E2)、WXF和中文记谱, 只接受当前局面的合法着法
+pub fn parse_move(board: &Board, text: &str) -> Result<Move, String> {
+    let text = text.trim();
+    let iccs: String = text.chars().filter(|&c| c != '-').collect::<String>().to_lowercase();
+    if let Ok(mv) = iccs.parse::<Move>() {
+        return if movegen::is_legal_move(board, mv) { Ok(mv) } else { Err(format!("不合法的着法: {}", text)) };
+    }
+    if text.is_ascii() {
+        wxf::parse_wxf(board, text)
+    } else {
+        parse_chinese(board, text)
+    }
+}
+
+pub const fn get_piece_name(piece: Piece) -> char {
+    match (piece.color, piece.kind) {
+        (Color::Red, PieceKind::King) => '帅',
+        (Color::Black, PieceKind::King) => '将',
+        (Color::Red, PieceKind::Advisor) => '仕',
+        (Color::Black, PieceKind::Advisor) => '士',
+        (Color::Red, PieceKind::Bishop) => '相',
+        (Color::Black, PieceKind::Bishop) => '象',
+        (_, PieceKind::Knight) => '马',
+        (_, PieceKind::Rook) => '车',
+        (_, PieceKind::Cannon) => '炮',
+        (Color::Red, PieceKind::Pawn) => '兵',
+        (Color::Black, PieceKind::Pawn) => '卒',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_red_board_to_fen() {
+        let expected_fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR w - - 0 1";
+        let mut board = Board::startpos();
+        board.set("h2".parse().unwrap(), None);
+        board.set("e2".parse().unwrap(), Piece::from_char('C'));
+        assert_eq!(board.to_string(), expected_fen);
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let board = Board::startpos();
+        let parse = |text| parse_move(&board, text).map(|mv| mv.to_string());
+        assert_eq!(parse("h2e2").unwrap(), "h2e2");
+        assert_eq!(parse(" H2-E2 ").unwrap(), "h2e2");
+        assert_eq!(parse("炮二平五").unwrap(), "h2e2");
+        assert_eq!(parse("C2=5").unwrap(), "h2e2");
+        assert_eq!(parse("C2.5").unwrap(), "h2e2");
+        // 按行棋方解析
+        assert_eq!(parse("马8进7").unwrap(), "b0c2");
+        assert_eq!(parse_move(&board.with_turn(Color::Black), "马8进7").unwrap().to_string(), "h9g7");
+        assert!(parse("e0e2").is_err());
+        assert!(parse("炮二进九").is_err());
+        assert!(parse("C2+9").is_err());
+        assert!(parse("abc").is_err());
+    }
+
+    #[test]
+    fn test_fen_to_board() {
+        let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR";
+        let board: Board = fen.parse().unwrap();
+        assert_eq!(board.turn, Color::Red);
+        assert_eq!(board.get("e2".parse().unwrap()), Piece::from_char('C'));
+        assert_eq!(board.get("h2".parse().unwrap()), None);
+        assert_eq!(board.get("e9".parse().unwrap()), Piece::from_char('k'));
+        assert_eq!(board.pieces().count(), 32);
+    }
+
+    #[test]
+    fn test_board_check() {
+        let fen = "rnbakaanr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR w";
+        assert!(fen.parse::<Board>().is_err());
+        let mut board = Board::startpos();
+        board.set("g9".parse().unwrap(), Piece::from_char('a'));
+        assert!(!board.is_valid());
+    }
+
+    #[test]
+    fn test_board_diff() {
+        let old: Board = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR w".parse().unwrap();
+        let new: Board = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P2PP1P/1C2C4/9/RNBAKABNR w".parse().unwrap();
+        match old.diff(&new) {
+            BoardChangeState::Move(changed) => assert_eq!(changed.mv().to_string(), "e3f3"),
+            state => panic!("{:?}", state),
+        }
+        assert!(matches!(old.diff(&old), BoardChangeState::Unknown));
+    }
+
+    #[test]
+    fn test_translate_pv() {
+        let pvs: Vec<String> = ["h2e2", "h9g7", "e2e6", "a0a1"].iter().map(|pv| pv.to_string()).collect();
+        let (moves, wxf) = translate_pv(&Board::startpos(), &pvs, Locale::Simplified);
+        assert_eq!(moves, ["炮二平五", "马8进7", "炮五进四"]);
+        assert_eq!(wxf, ["C2=5", "H8+7", "C5+4"]);
+        // 第一步就不合法时为空
+        assert!(translate_pv(&Board::startpos(), &pvs[1..], Locale::Simplified).0.is_empty());
+    }
+
+    #[test]
+    fn test_board_map() {
+        let board: Board = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/R8/1NBAKABNR w".parse().unwrap();
+        let map = board.map();
+        assert_eq!(map.len(), 90);
+        println!("{:?}", map)
+    }
+
+    #[test]
+    fn test_board_fix() {
+        let mut board: Board = "RNBAKABNR/9/4C2C1/P1P1P1P1P/9/9/p1p1p1p1p/1c5c1/9/rnbakabnr w".parse().unwrap();
+        board.fix(&Camp::Black);
+        assert_eq!(board.to_string(), "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR w - - 0 1");
+    }
+}
@@ -0,0 +1,385 @@
+use super::Board;
+use super::Color;
+use super::GameOver;
+use super::GameStatus;
+use super::Move;
+use super::PieceKind;
+use super::Square;
+
+// 车、炮、帅的直线方向
+const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+// 仕的斜线方向
+const DIAGONAL: [(isize, isize); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+// 马的走法: (目标偏移, 马腿偏移)
+const KNIGHT: [((isize, isize), (isize, isize)); 8] = [
+    ((-1, -2), (0, -1)),
+    ((1, -2), (0, -1)),
+    ((-1, 2), (0, 1)),
+    ((1, 2), (0, 1)),
+    ((-2, -1), (-1, 0)),
+    ((-2, 1), (-1, 0)),
+    ((2, -1), (1, 0)),
+    ((2, 1), (1, 0)),
+];
+
+// 九宫范围
+#[inline]
+fn in_palace(color: Color, sq: Square) -> bool {
+    (3..=5).contains(&sq.x())
+        && match color {
+            Color::Red => sq.y() >= 7,
+            Color::Black => sq.y() <= 2,
+        }
+}
+
+// 是否在本方阵地(未过河)
+#[inline]
+fn own_side(color: Color, y: usize) -> bool {
+    match color {
+        Color::Red => y >= 5,
+        Color::Black => y <= 4,
+    }
+}
+
+// 兵卒前进方向
+#[inline]
+fn forward(color: Color) -> isize {
+    match color {
+        Color::Red => -1,
+        Color::Black => 1,
+    }
+}
+
+#[inline]
+fn is_friend(board: &Board, sq: Square, color: Color) -> bool { board.get(sq).is_some_and(|p| p.color == color) }
+
+#[inline]
+fn is_piece(board: &Board, sq: Square, color: Color, kind: PieceKind) -> bool {
+    board.get(sq).is_some_and(|p| p.color == color && p.kind == kind)
+}
+
+// 生成from处棋子的伪合法走法(不考虑送将)
+fn piece_targets(board: &Board, from: Square, targets: &mut Vec<Square>) {
+    let Some(piece) = board.get(from) else { return };
+    let color = piece.color;
+    let mut push = |to: Square| {
+        if !is_friend(board, to, color) {
+            targets.push(to);
+        }
+    };
+
+    match piece.kind {
+        PieceKind::King => {
+            for (dx, dy) in ORTHOGONAL {
+                if let Some(to) = from.offset(dx, dy) {
+                    if in_palace(color, to) {
+                        push(to);
+                    }
+                }
+            }
+        }
+        PieceKind::Advisor => {
+            for (dx, dy) in DIAGONAL {
+                if let Some(to) = from.offset(dx, dy) {
+                    if in_palace(color, to) {
+                        push(to);
+                    }
+                }
+            }
+        }
+        PieceKind::Bishop => {
+            for (dx, dy) in DIAGONAL {
+                // 塞象眼
+                let Some(eye) = from.offset(dx, dy) else { continue };
+                let Some(to) = from.offset(dx * 2, dy * 2) else { continue };
+                if board.get(eye).is_none() && own_side(color, to.y()) {
+                    push(to);
+                }
+            }
+        }
+        PieceKind::Knight => {
+            for ((dx, dy), (lx, ly)) in KNIGHT {
+                // 蹩马腿
+                let Some(to) = from.offset(dx, dy) else { continue };
+                let leg = from.offset(lx, ly).unwrap();
+                if board.get(leg).is_none() {
+                    push(to);
+                }
+            }
+        }
+        PieceKind::Rook => {
+            for (dx, dy) in ORTHOGONAL {
+                let mut cur = from;
+                while let Some(to) = cur.offset(dx, dy) {
+                    push(to);
+                    if board.get(to).is_some() {
+                        break;
+                    }
+                    cur = to;
+                }
+            }
+        }
+        PieceKind::Cannon => {
+            for (dx, dy) in ORTHOGONAL {
+                let mut cur = from;
+                let mut screen = false;
+                while let Some(to) = cur.offset(dx, dy) {
+                    match board.get(to) {
+                        None if !screen => push(to),
+                        None => {}
+                        Some(_) if !screen => screen = true,
+                        // 隔山打子
+                        Some(_) => {
+                            push(to);
+                            break;
+                        }
+                    }
+                    cur = to;
+                }
+            }
+        }
+        PieceKind::Pawn => {
+            if let Some(to) = from.offset(0, forward(color)) {
+                push(to);
+            }
+            if !own_side(color, from.y()) {
+                for dx in [-1, 1] {
+                    if let Some(to) = from.offset(dx, 0) {
+                        push(to);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 判断sq是否受到指定一方的攻击
+fn attacked(board: &Board, sq: Square, by: Color) -> bool {
+    // 车、炮、对面将
+    for (dx, dy) in ORTHOGONAL {
+        let mut cur = sq;
+        let mut screen = false;
+        while let Some(next) = cur.offset(dx, dy) {
+            if let Some(piece) = board.get(next) {
+                if screen {
+                    if piece.color == by && piece.kind == PieceKind::Cannon {
+                        return true;
+                    }
+                    break;
+                }
+                if piece.color == by {
+                    match piece.kind {
+                        PieceKind::Rook => return true,
+                        // 将帅照面
+                        PieceKind::King if dx == 0 => return true,
+                        _ => {}
+                    }
+                }
+                screen = true;
+            }
+            cur = next;
+        }
+    }
+
+    // 马: 从目标点反推马的位置, 马腿在马的一侧
+    for ((dx, dy), (lx, ly)) in KNIGHT {
+        let Some(knight) = sq.offset(-dx, -dy) else { continue };
+        if is_piece(board, knight, by, PieceKind::Knight) && board.get(knight.offset(lx, ly).unwrap()).is_none() {
+            return true;
+        }
+    }
+
+    // 兵卒: 正面和过河后的两侧
+    if let Some(pawn) = sq.offset(0, -forward(by)) {
+        if is_piece(board, pawn, by, PieceKind::Pawn) {
+            return true;
+        }
+    }
+    if !own_side(by, sq.y()) {
+        for dx in [-1, 1] {
+            if let Some(pawn) = sq.offset(dx, 0) {
+                if is_piece(board, pawn, by, PieceKind::Pawn) {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+// 判断一方是否被将军(包括将帅照面)
+pub fn in_check(board: &Board, color: Color) -> bool {
+    match board.king(color) {
+        Some(king) => attacked(board, king, color.opponent()),
+        None => true,
+    }
+}
+
+// 生成行棋方的全部合法走法
+pub fn legal_moves(board: &Board) -> Vec<Move> {
+    let color = board.turn;
+    let mut moves = Vec::with_capacity(64);
+    let mut targets = Vec::with_capacity(17);
+    for (from, piece) in board.pieces() {
+        if piece.color != color {
+            continue;
+        }
+        targets.clear();
+        piece_targets(board, from, &mut targets);
+        for &to in &targets {
+            // 走完后不能被将军(牵制、送将、照面)
+            let mv = Move::new(from, to);
+            if !in_check(&board.make_move(mv), color) {
+                moves.push(mv);
+            }
+        }
+    }
+    moves
+}
+
+// 判断走法对行棋方是否合法
+pub fn is_legal_move(board: &Board, mv: Move) -> bool {
+    match board.get(mv.from) {
+        Some(piece) if piece.color == board.turn => {
+            let mut targets = Vec::with_capacity(17);
+            piece_targets(board, mv.from, &mut targets);
+            targets.contains(&mv.to) && !in_check(&board.make_move(mv), board.turn)
+        }
+        _ => false,
+    }
+}
+
+// 判断行棋方的局面状态
+pub fn game_status(board: &Board) -> GameStatus {
+    let check = in_check(board, board.turn);
+    match (legal_moves(board).is_empty(), check) {
+        (true, true) => GameStatus::Checkmate,
+        (true, false) => GameStatus::Stalemate,
+        (false, true) => GameStatus::Check,
+        (false, false) => GameStatus::Ongoing,
+    }
+}
+
+// 行棋方被将死或困毙时返回对局结果, 象棋中困毙同样判负
+pub fn game_over(board: &Board) -> Option<GameOver> {
+    match game_status(board) {
+        reason @ (GameStatus::Checkmate | GameStatus::Stalemate) => {
+            Some(GameOver { winner: board.turn.opponent(), reason })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> Board { fen.parse().unwrap() }
+
+    fn iccs_list(board: &Board) -> Vec<String> {
+        let mut list: Vec<String> = legal_moves(board).iter().map(Move::to_string).collect();
+        list.sort();
+        list
+    }
+
+    #[test]
+    fn test_startpos_moves() {
+        let start = Board::startpos();
+        assert_eq!(legal_moves(&start).len(), 44);
+        assert_eq!(legal_moves(&start.with_turn(Color::Black)).len(), 44);
+    }
+
+    #[test]
+    fn test_knight_leg() {
+        // 马腿被自己的兵蹩住, 只能向两侧跳
+        let moves = iccs_list(&board("4k4/9/9/9/9/9/9/4P4/4N4/3K5 w"));
+        assert!(moves.contains(&"e1c2".to_string()));
+        assert!(moves.contains(&"e1g2".to_string()));
+        assert!(!moves.contains(&"e1d3".to_string()));
+        assert!(!moves.contains(&"e1f3".to_string()));
+    }
+
+    #[test]
+    fn test_elephant_eye_and_river() {
+        // 象眼被塞, 且象不能过河
+        let moves = iccs_list(&board("3k5/9/9/9/9/2B6/3P5/9/9/4K4 w"));
+        assert!(moves.contains(&"c4a2".to_string()));
+        assert!(!moves.contains(&"c4e2".to_string()));
+        assert!(!moves.contains(&"c4e6".to_string()));
+        assert!(!moves.contains(&"c4a6".to_string()));
+    }
+
+    #[test]
+    fn test_cannon_screen() {
+        let moves = iccs_list(&board("4k4/9/9/9/4r4/9/4P4/9/4C4/3K5 w"));
+        assert!(moves.contains(&"e1e5".to_string()));
+        assert!(!moves.contains(&"e1e3".to_string()));
+        assert!(moves.contains(&"e1e2".to_string()));
+    }
+
+    #[test]
+    fn test_flying_general() {
+        // 帅不能走到与将照面的纵线
+        let moves = iccs_list(&board("3k5/9/9/9/9/9/9/9/9/4K4 w"));
+        assert!(!moves.contains(&"e0d0".to_string()));
+        assert!(moves.contains(&"e0f0".to_string()));
+        assert!(moves.contains(&"e0e1".to_string()));
+    }
+
+    #[test]
+    fn test_pin() {
+        // 车被牵制在中路, 不能离开纵线
+        let pinned = board("4k4/9/9/9/9/9/9/4R4/9/4K4 w");
+        assert!(legal_moves(&pinned).iter().filter(|mv| mv.from.y() == 7).all(|mv| mv.to.x() == 4));
+        assert!(is_legal_move(&pinned, "e2e9".parse().unwrap()));
+        assert!(!is_legal_move(&pinned, "e2d2".parse().unwrap()));
+        // 不是行棋方的棋子
+        assert!(!is_legal_move(&pinned.with_turn(Color::Black), "e2e9".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_pawn_moves() {
+        let moves = iccs_list(&board("4k4/9/9/9/2P6/4P4/9/9/9/3K5 w"));
+        // 未过河只能前进
+        assert!(moves.contains(&"e4e5".to_string()));
+        assert!(!moves.contains(&"e4d4".to_string()));
+        // 过河可以横走
+        assert!(moves.contains(&"c5b5".to_string()));
+        assert!(moves.contains(&"c5d5".to_string()));
+        assert!(moves.contains(&"c5c6".to_string()));
+    }
+
+    #[test]
+    fn test_in_check() {
+        assert!(!in_check(&board("4k4/9/9/9/9/9/9/9/4r4/3K5 w"), Color::Red));
+        assert!(in_check(&board("4k4/9/9/9/9/9/9/9/3r5/3K5 w"), Color::Red));
+        assert!(!in_check(&board("4k4/9/9/9/9/9/9/9/3nP4/5K3 w"), Color::Red));
+        assert!(in_check(&board("4k4/9/9/9/9/9/9/4n4/9/5K3 w"), Color::Red));
+    }
+
+    #[test]
+    fn test_game_status() {
+        let start = Board::startpos();
+        assert_eq!(game_status(&start), GameStatus::Ongoing);
+        assert!(game_over(&start).is_none());
+
+        // 车将军, 可以躲避
+        assert_eq!(game_status(&board("3k5/9/9/9/9/9/9/9/9/3R1K3 b")), GameStatus::Check);
+
+        // 双车错杀
+        let mated = board("3k5/4R4/3R5/9/9/9/9/9/9/4K4 b");
+        assert_eq!(game_status(&mated), GameStatus::Checkmate);
+        let result = game_over(&mated).unwrap();
+        assert_eq!(result.winner, Color::Red);
+        assert_eq!(result.reason, GameStatus::Checkmate);
+    }
+
+    #[test]
+    fn test_stalemate() {
+        // 黑将无子可动且未被将军, 困毙
+        let stalemate = board("3k5/2P6/9/9/9/9/9/9/9/4K4 b");
+        assert_eq!(game_status(&stalemate), GameStatus::Stalemate);
+        assert_eq!(game_over(&stalemate).unwrap().winner, Color::Red);
+    }
+}
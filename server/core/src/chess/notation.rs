@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use super::movegen::is_legal_move;
+use super::Board;
+use super::Color;
+use super::Move;
+use super::Piece;
+use super::PieceKind;
+use super::Square;
+
+// 前后次序: 前、中、后或从前往后的序号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Front,
+    Middle,
+    Back,
+    Nth(usize),
+}
+
+impl Order {
+    // 在count个重叠棋子中的下标
+    pub fn index(self, count: usize) -> Option<usize> {
+        match self {
+            Order::Front => Some(0),
+            Order::Middle if count == 3 => Some(1),
+            Order::Back => count.checked_sub(1),
+            Order::Nth(n) => n.checked_sub(1),
+            _ => None,
+        }
+        .filter(|&i| i < count)
+    }
+}
+
+// 走法方向: 进、退、平
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Advance,
+    Retreat,
+    Traverse,
+}
+
+// 要走的棋子: 兵种加纵线或前后次序
+#[derive(Debug, Clone, Copy)]
+pub struct Mover {
+    pub kind: PieceKind,
+    pub file: Option<usize>,
+    pub order: Option<Order>,
+}
+
+// 同一纵线上有多个同种棋子时的位置信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tandem {
+    // 从前往后的下标
+    pub index: usize,
+    // 该纵线上的数量
+    pub count: usize,
+    // 是否有多条纵线都有重叠
+    pub multi_file: bool,
+}
+
+// 纵线序号换算为列, 红方从右往左数, 黑方从左往右数
+pub fn file_to_x(color: Color, file: usize) -> usize {
+    match color {
+        Color::Red => 9 - file,
+        Color::Black => file - 1,
+    }
+}
+
+pub fn x_to_file(color: Color, x: usize) -> usize {
+    match color {
+        Color::Red => 9 - x,
+        Color::Black => x + 1,
+    }
+}
+
+// 距离对方底线的行数, 越小越靠前
+pub fn depth(color: Color, sq: Square) -> usize {
+    match color {
+        Color::Red => sq.y(),
+        Color::Black => 9 - sq.y(),
+    }
+}
+
+// 士、象、马斜着走, 用目标纵线表示
+fn is_diagonal(kind: PieceKind) -> bool { matches!(kind, PieceKind::Advisor | PieceKind::Bishop | PieceKind::Knight) }
+
+// 走法的方向和目标数字: 直走为步数, 平移和斜走为目标纵线
+pub fn action_of(piece: Piece, mv: Move) -> (Action, usize) {
+    let steps = depth(piece.color, mv.from).abs_diff(depth(piece.color, mv.to));
+    let action = match depth(piece.color, mv.to).cmp(&depth(piece.color, mv.from)) {
+        Ordering::Less => Action::Advance,
+        Ordering::Greater => Action::Retreat,
+        Ordering::Equal => Action::Traverse,
+    };
+    if action == Action::Traverse || is_diagonal(piece.kind) {
+        (action, x_to_file(piece.color, mv.to.x()))
+    } else {
+        (action, steps)
+    }
+}
+
+// 按方向和目标数字计算落点
+pub fn destination(from: Square, piece: Piece, action: Action, target: usize) -> Option<Square> {
+    let forward = match (piece.color, action) {
+        (_, Action::Traverse) => 0,
+        (Color::Red, Action::Advance) | (Color::Black, Action::Retreat) => -1,
+        (Color::Red, Action::Retreat) | (Color::Black, Action::Advance) => 1,
+    };
+    let dx = file_to_x(piece.color, target) as isize - from.x() as isize;
+    if !is_diagonal(piece.kind) {
+        return match action {
+            Action::Traverse => from.offset(dx, 0),
+            _ => from.offset(0, forward * target as isize),
+        };
+    }
+    let dy = match (piece.kind, dx.abs()) {
+        (PieceKind::Advisor, 1) => 1,
+        (PieceKind::Bishop, 2) => 2,
+        (PieceKind::Knight, 1) => 2,
+        (PieceKind::Knight, 2) => 1,
+        _ => return None,
+    };
+    if action == Action::Traverse {
+        return None;
+    }
+    from.offset(dx, forward * dy)
+}
+
+// 车、马、炮、兵在同一纵线重叠时需要区分前后, 士、象靠进退即可区分
+pub fn tandem(board: &Board, sq: Square) -> Option<Tandem> {
+    let piece = board.get(sq)?;
+    if !matches!(piece.kind, PieceKind::Rook | PieceKind::Knight | PieceKind::Cannon | PieceKind::Pawn) {
+        return None;
+    }
+    let mut files = [0usize; 9];
+    for (other, _) in board.pieces().filter(|(_, p)| *p == piece) {
+        files[other.x()] += 1;
+    }
+    let count = files[sq.x()];
+    if count < 2 {
+        return None;
+    }
+    let index = (0..10)
+        .map(|y| Square::new(sq.x(), y))
+        .filter(|&other| board.get(other) == Some(piece) && depth(piece.color, other) < depth(piece.color, sq))
+        .count();
+    let multi_file = files.iter().filter(|&&n| n > 1).count() > 1;
+    Some(Tandem { index, count, multi_file })
+}
+
+// 按纵线和前后次序找出唯一的合法走法, text仅用于错误信息
+pub fn resolve(board: &Board, mover: Mover, action: Action, target: usize, text: &str) -> Result<Move, String> {
+    let piece = Piece::new(board.turn, mover.kind);
+    let all: Vec<Square> = board.pieces().filter(|(_, p)| *p == piece).map(|(sq, _)| sq).collect();
+    let mut froms: Vec<Square> = match mover.file {
+        Some(file) => all.iter().copied().filter(|sq| sq.x() == file_to_x(piece.color, file)).collect(),
+        None => all.clone(),
+    };
+    if let Some(order) = mover.order {
+        // 只在有重叠棋子的纵线中选择
+        froms.retain(|sq| all.iter().filter(|other| other.x() == sq.x()).count() > 1);
+        froms.sort_by_key(|&sq| (x_to_file(piece.color, sq.x()), depth(piece.color, sq)));
+        let files = froms.iter().map(|sq| sq.x()).collect::<HashSet<_>>().len();
+        if files > 1 && !matches!(order, Order::Nth(_)) {
+            return Err(format!("着法有歧义, 多条纵线上都有重叠的棋子: {}", text));
+        }
+        froms = match order.index(froms.len()) {
+            Some(i) => vec![froms[i]],
+            None => Vec::new(),
+        };
+    }
+    if froms.is_empty() {
+        return Err(format!("找不到要走的棋子: {}", text));
+    }
+
+    let moves: Vec<Move> = froms
+        .iter()
+        .filter_map(|&from| destination(from, piece, action, target).map(|to| Move::new(from, to)))
+        .filter(|&mv| is_legal_move(board, mv))
+        .collect();
+    match moves.as_slice() {
+        [mv] => Ok(*mv),
+        [] => Err(format!("不合法的着法: {}", text)),
+        _ => Err(format!("着法有歧义: {}", text)),
+    }
+}
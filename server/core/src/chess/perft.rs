@@ -0,0 +1,90 @@
+use super::movegen::legal_moves;
+use super::Board;
+
+// 统计指定深度的叶子节点数量
+pub fn perft(board: &Board, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = legal_moves(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves.iter().map(|&mv| perft(&board.make_move(mv), depth - 1)).sum()
+}
+
+// 按根节点走法拆分perft结果, 按ICCS排序, 便于和引擎的 go perft 输出逐项对比
+pub fn divide(board: &Board, depth: usize) -> Vec<(String, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut result: Vec<(String, u64)> =
+        legal_moves(board).iter().map(|&mv| (mv.to_string(), perft(&board.make_move(mv), depth - 1))).collect();
+    result.sort();
+    result
+}
+
+// 对比两份divide结果, 返回不一致的走法: (走法, 本地节点数, 对方节点数)
+pub fn divide_diff(ours: &[(String, u64)], theirs: &[(String, u64)]) -> Vec<(String, Option<u64>, Option<u64>)> {
+    let mut diff = Vec::new();
+    for (mv, nodes) in ours {
+        let other = theirs.iter().find(|(m, _)| m == mv).map(|(_, n)| *n);
+        if other != Some(*nodes) {
+            diff.push((mv.clone(), Some(*nodes), other));
+        }
+    }
+    for (mv, nodes) in theirs {
+        if !ours.iter().any(|(m, _)| m == mv) {
+            diff.push((mv.clone(), None, Some(*nodes)));
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 公开的中国象棋perft数据 (FEN, 各深度节点数)
+    const PERFT_SUITE: [(&str, &[u64]); 5] = [
+        ("rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w", &[44, 1920, 79666, 3290240]),
+        ("r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w", &[38, 1128, 43929, 1339047]),
+        ("1cbak4/9/n2a5/2p1p3p/5cp2/2n2N3/6PCP/3AB4/2C6/3A1K1N1 w", &[7, 281, 8620, 326201]),
+        ("CRN1k1b2/3ca4/4ba3/9/2nr5/9/9/4B4/4A4/4KA3 w", &[28, 516, 14808]),
+        ("R1N1k1b2/9/3aba3/9/2nr5/2B6/9/4B4/4A4/4KA3 w", &[21, 364, 7626, 162837]),
+    ];
+
+    fn run_suite(max_depth: usize) {
+        for (fen, counts) in PERFT_SUITE {
+            let board: Board = fen.parse().unwrap();
+            for (depth, &expected) in counts.iter().enumerate().take(max_depth) {
+                assert_eq!(perft(&board, depth + 1), expected, "{} depth {}", fen, depth + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_perft() { run_suite(3); }
+
+    #[test]
+    #[ignore = "耗时较长, 使用 cargo test --release -- --ignored 运行"]
+    fn test_perft_deep() { run_suite(4); }
+
+    #[test]
+    fn test_divide() {
+        let (fen, counts) = PERFT_SUITE[0];
+        let board: Board = fen.parse().unwrap();
+        let result = divide(&board, 2);
+        assert_eq!(result.len() as u64, counts[0]);
+        assert_eq!(result.iter().map(|(_, n)| n).sum::<u64>(), counts[1]);
+        assert!(divide_diff(&result, &result).is_empty());
+
+        let mut other = result.clone();
+        other[0].1 += 1;
+        other.pop();
+        let diff = divide_diff(&result, &other);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].0, result[0].0);
+        assert_eq!(diff[1], (result.last().unwrap().0.clone(), Some(result.last().unwrap().1), None));
+    }
+}
@@ -0,0 +1,283 @@
+use std::cmp::Ordering;
+use std::iter::once;
+
+use serde::Serialize;
+
+use super::movegen::in_check;
+use super::movegen::legal_moves;
+use super::notation::depth;
+use super::Board;
+use super::Color;
+use super::Move;
+use super::Piece;
+use super::PieceKind;
+use super::Square;
+
+// 同一局面出现三次视为循环
+const REPETITION_COUNT: usize = 3;
+
+// 单步着法的性质, 将军优先于捉子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Check,
+    Chase,
+    Idle,
+}
+
+// 循环中一方着法的性质
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Pattern {
+    Idle,           // 闲着
+    PerpetualChase, // 长捉
+    CheckChase,     // 一将一捉
+    PerpetualCheck, // 长将
+}
+
+impl Pattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Idle => "闲着",
+            Pattern::PerpetualChase => "长捉",
+            Pattern::CheckChase => "一将一捉",
+            Pattern::PerpetualCheck => "长将",
+        }
+    }
+
+    // 违例程度, 一将一捉按长将处理, 重于长捉
+    fn severity(self) -> u8 {
+        match self {
+            Pattern::Idle => 0,
+            Pattern::PerpetualChase => 1,
+            Pattern::CheckChase | Pattern::PerpetualCheck => 2,
+        }
+    }
+}
+
+// 循环局面中双方的着法性质
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Repetition {
+    pub red: Pattern,
+    pub black: Pattern,
+}
+
+impl Repetition {
+    pub fn pattern(&self, color: Color) -> Pattern {
+        match color {
+            Color::Red => self.red,
+            Color::Black => self.black,
+        }
+    }
+
+    // 违例更重的一方必须变着, 否则判负; 双方相同时(如双方闲着、双方长将)判和
+    pub fn loser(&self) -> Option<Color> {
+        match self.red.severity().cmp(&self.black.severity()) {
+            Ordering::Greater => Some(Color::Red),
+            Ordering::Less => Some(Color::Black),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    mv: Move,
+    color: Color,
+    board: Board,
+    kind: Kind,
+}
+
+// 对局的着法历史, 用于判断循环局面
+#[derive(Debug, Clone)]
+pub struct History {
+    start: Board,
+    entries: Vec<Entry>,
+}
+
+impl History {
+    pub fn new(start: Board) -> Self { Self { start, entries: Vec::new() } }
+
+    // 起始局面, 行棋方以第一步为准
+    pub fn start(&self) -> Board { self.start }
+
+    // 当前局面
+    pub fn board(&self) -> Board { self.entries.last().map_or(self.start, |entry| entry.board) }
+
+    // 起始局面之后的全部着法
+    pub fn moves(&self) -> Vec<Move> { self.entries.iter().map(|entry| entry.mv).collect() }
+
+    // 记录一步棋, 行棋方取走动棋子的颜色, 不检查合法性
+    pub fn push(&mut self, mv: Move) {
+        let Some(piece) = self.board().get(mv.from) else { return };
+        if self.entries.is_empty() {
+            // 识别出的棋盘行棋方不可靠, 以第一步为准
+            self.start.turn = piece.color;
+        }
+        let before = self.board().with_turn(piece.color);
+        let after = before.make_move(mv);
+        let kind = if in_check(&after, piece.color.opponent()) {
+            Kind::Check
+        } else if chases(&before, &after, piece.color) {
+            Kind::Chase
+        } else {
+            Kind::Idle
+        };
+        self.entries.push(Entry { mv, color: piece.color, board: after, kind });
+    }
+
+    // 当前局面第三次出现时, 按亚洲规则判断双方着法的性质
+    pub fn repetition(&self) -> Option<Repetition> {
+        let key = self.board().zobrist();
+        let found: Vec<usize> = once(self.start.zobrist())
+            .chain(self.entries.iter().map(|entry| entry.board.zobrist()))
+            .enumerate()
+            .filter(|&(_, k)| k == key)
+            .map(|(i, _)| i)
+            .collect();
+        if found.len() < REPETITION_COUNT {
+            return None;
+        }
+        let cycle = &self.entries[found[found.len() - REPETITION_COUNT]..];
+        let kinds = |color| cycle.iter().filter(|entry| entry.color == color).map(|entry| entry.kind).collect();
+        Some(Repetition { red: pattern(kinds(Color::Red)), black: pattern(kinds(Color::Black)) })
+    }
+
+    // 走这步棋是否会形成必须变着的循环, 返回违例的性质
+    pub fn forbidden(&self, mv: Move) -> Option<Pattern> {
+        let color = self.board().get(mv.from)?.color;
+        let mut next = self.clone();
+        next.push(mv);
+        let repetition = next.repetition()?;
+        (repetition.loser() == Some(color)).then(|| repetition.pattern(color))
+    }
+}
+
+// 一方在循环中每步都将军或捉子才算违例
+fn pattern(kinds: Vec<Kind>) -> Pattern {
+    let checks = kinds.iter().filter(|&&kind| kind == Kind::Check).count();
+    let chases = kinds.iter().filter(|&&kind| kind == Kind::Chase).count();
+    match (checks, chases) {
+        _ if kinds.is_empty() => Pattern::Idle,
+        (n, 0) if n == kinds.len() => Pattern::PerpetualCheck,
+        (0, n) if n == kinds.len() => Pattern::PerpetualChase,
+        (m, n) if m + n == kinds.len() => Pattern::CheckChase,
+        _ => Pattern::Idle,
+    }
+}
+
+// 子力价值, 只用于比较捉子双方的大小
+fn value(piece: Piece, sq: Square) -> u8 {
+    match piece.kind {
+        PieceKind::King => 0,
+        PieceKind::Rook => 9,
+        PieceKind::Cannon => 5,
+        PieceKind::Knight => 4,
+        PieceKind::Advisor | PieceKind::Bishop => 2,
+        PieceKind::Pawn if crossed(piece.color, sq) => 2,
+        PieceKind::Pawn => 1,
+    }
+}
+
+fn crossed(color: Color, sq: Square) -> bool { depth(color, sq) < 5 }
+
+// 吃子走法是否构成捉: 将帅和兵卒捉子不算捉, 未过河的兵卒不算被捉;
+// 目标无根, 或者目标价值大于进攻的棋子(如马捉车)
+fn is_chase(board: &Board, mv: Move) -> bool {
+    let (Some(attacker), Some(target)) = (board.get(mv.from), board.get(mv.to)) else { return false };
+    if matches!(attacker.kind, PieceKind::King | PieceKind::Pawn) || target.kind == PieceKind::King {
+        return false;
+    }
+    if target.kind == PieceKind::Pawn && !crossed(target.color, mv.to) {
+        return false;
+    }
+    if value(target, mv.to) > value(attacker, mv.from) {
+        return true;
+    }
+    !legal_moves(&board.make_move(mv)).iter().any(|other| other.to == mv.to)
+}
+
+// 一方当前捉住的对方棋子
+fn targets(board: &Board, color: Color) -> Vec<Square> {
+    let board = board.with_turn(color);
+    legal_moves(&board).into_iter().filter(|&mv| is_chase(&board, mv)).map(|mv| mv.to).collect()
+}
+
+// 走完后是否捉住了走之前没有捉住的棋子
+fn chases(before: &Board, after: &Board, color: Color) -> bool {
+    let old = targets(before, color);
+    targets(after, color).iter().any(|sq| !old.contains(sq))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(fen: &str, moves: &[&str]) -> History {
+        let mut history = History::new(fen.parse().unwrap());
+        for mv in moves {
+            history.push(mv.parse().unwrap());
+        }
+        history
+    }
+
+    fn kind(fen: &str, mv: &str) -> Kind { play(fen, &[mv]).entries[0].kind }
+
+    #[test]
+    fn test_kind() {
+        // 车捉无根炮
+        assert_eq!(kind("5k3/9/c8/9/9/9/2R6/9/9/3K5 w", "c3a3"), Kind::Chase);
+        // 车捉有根炮不算捉
+        assert_eq!(kind("1n3k3/9/c8/9/9/9/2R6/9/9/3K5 w", "c3a3"), Kind::Idle);
+        // 马捉有根车仍然算捉
+        assert_eq!(kind("1n3k3/9/r8/9/9/9/2N6/9/9/3K5 w", "c3b5"), Kind::Chase);
+        // 兵捉子不算捉
+        assert_eq!(kind("5k3/9/9/9/1r7/P8/9/9/9/3K5 w", "a4a5"), Kind::Idle);
+        assert_eq!(kind("3k5/9/9/9/R8/9/9/9/9/5K3 w", "a5d5"), Kind::Check);
+    }
+
+    #[test]
+    fn test_perpetual_check() {
+        let fen = "3k5/9/9/9/R8/9/9/9/9/5K3 w";
+        let moves = ["a5d5", "d9e9", "d5e5", "e9d9", "e5d5", "d9e9", "d5e5", "e9d9", "e5d5"];
+        let history = play(fen, &moves[..8]);
+        assert_eq!(history.start().turn, Color::Red);
+        assert_eq!(history.moves().iter().map(|mv| mv.to_string()).collect::<Vec<_>>(), moves[..8]);
+        assert_eq!(history.repetition(), None);
+        assert_eq!(history.forbidden("e5d5".parse().unwrap()), Some(Pattern::PerpetualCheck));
+        assert_eq!(history.forbidden("e5e4".parse().unwrap()), None);
+        let repetition = play(fen, &moves).repetition().unwrap();
+        assert_eq!(repetition, Repetition { red: Pattern::PerpetualCheck, black: Pattern::Idle });
+        assert_eq!(repetition.loser(), Some(Color::Red));
+    }
+
+    #[test]
+    fn test_perpetual_chase() {
+        let fen = "5k3/9/c8/9/9/9/2R6/9/9/3K5 w";
+        let moves = ["c3a3", "a7b7", "a3b3", "b7a7", "b3a3", "a7b7", "a3b3", "b7a7", "b3a3"];
+        let repetition = play(fen, &moves).repetition().unwrap();
+        assert_eq!(repetition, Repetition { red: Pattern::PerpetualChase, black: Pattern::Idle });
+        assert_eq!(repetition.loser(), Some(Color::Red));
+    }
+
+    #[test]
+    fn test_idle() {
+        let fen = "5k3/9/9/9/9/9/9/9/9/3K5 w";
+        let moves = ["d0d1", "f9f8", "d1d0", "f8f9", "d0d1", "f9f8", "d1d0", "f8f9"];
+        let repetition = play(fen, &moves).repetition().unwrap();
+        assert_eq!(repetition, Repetition { red: Pattern::Idle, black: Pattern::Idle });
+        assert_eq!(repetition.loser(), None);
+        // 识别的棋盘行棋方错误时以第一步为准
+        let fen = "5k3/9/9/9/9/9/9/9/9/3K5 b";
+        assert!(play(fen, &moves).repetition().is_some());
+    }
+
+    #[test]
+    fn test_pattern() {
+        assert_eq!(pattern(vec![Kind::Check, Kind::Chase, Kind::Check]), Pattern::CheckChase);
+        assert_eq!(pattern(vec![Kind::Check, Kind::Idle]), Pattern::Idle);
+        assert_eq!(pattern(vec![]), Pattern::Idle);
+        let repetition = Repetition { red: Pattern::PerpetualCheck, black: Pattern::PerpetualChase };
+        assert_eq!(repetition.loser(), Some(Color::Red));
+        let repetition = Repetition { red: Pattern::CheckChase, black: Pattern::PerpetualCheck };
+        assert_eq!(repetition.loser(), None);
+    }
+}
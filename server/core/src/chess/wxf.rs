@@ -0,0 +1,192 @@
+use super::notation::action_of;
+use super::notation::resolve;
+use super::notation::tandem;
+use super::notation::x_to_file;
+use super::notation::Action;
+use super::notation::Mover;
+use super::notation::Order;
+use super::Board;
+use super::Move;
+use super::PieceKind;
+
+fn kind_letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::King => 'K',
+        PieceKind::Advisor => 'A',
+        PieceKind::Bishop => 'E',
+        PieceKind::Knight => 'H',
+        PieceKind::Rook => 'R',
+        PieceKind::Cannon => 'C',
+        PieceKind::Pawn => 'P',
+    }
+}
+
+// 兼容 B(象) 和 N(马) 的写法
+fn parse_letter(c: char) -> Option<PieceKind> {
+    match c.to_ascii_uppercase() {
+        'K' => Some(PieceKind::King),
+        'A' => Some(PieceKind::Advisor),
+        'E' | 'B' => Some(PieceKind::Bishop),
+        'H' | 'N' => Some(PieceKind::Knight),
+        'R' => Some(PieceKind::Rook),
+        'C' => Some(PieceKind::Cannon),
+        'P' => Some(PieceKind::Pawn),
+        _ => None,
+    }
+}
+
+fn parse_digit(c: char) -> Option<usize> { c.to_digit(10).filter(|&n| n > 0).map(|n| n as usize) }
+
+fn digit(n: usize) -> char { char::from_digit(n as u32, 10).unwrap_or('?') }
+
+// 坐标走法转换为WXF记谱: 兵种字母 + 纵线 + 方向(+进 -退 =平) + 步数或目标纵线, 如 "C2=5"、"H8+7"
+// 同一纵线有两个同种棋子时用 +/- 代替纵线区分前后, 如 "+R+1"; 三个及以上时用从前往后的序号, 如 "2P=4";
+// 多条纵线都有重叠的兵时再用纵线代替兵种字母, 如 "+7=6"
+pub fn board_move_wxf(board: &Board, mv: Move) -> String {
+    let mut wxf = String::new();
+    let Some(piece) = board.get(mv.from) else { return wxf };
+    match tandem(board, mv.from) {
+        None => {
+            wxf.push(kind_letter(piece.kind));
+            wxf.push(digit(x_to_file(piece.color, mv.from.x())));
+        }
+        Some(tandem) => {
+            match (tandem.count, tandem.index) {
+                (2, 0) => wxf.push('+'),
+                (2, _) => wxf.push('-'),
+                (_, index) => wxf.push(digit(index + 1)),
+            }
+            if tandem.multi_file {
+                wxf.push(digit(x_to_file(piece.color, mv.from.x())));
+            } else {
+                wxf.push(kind_letter(piece.kind));
+            }
+        }
+    }
+    let (action, target) = action_of(piece, mv);
+    wxf.push(match action {
+        Action::Advance => '+',
+        Action::Retreat => '-',
+        Action::Traverse => '=',
+    });
+    wxf.push(digit(target));
+    wxf
+}
+
+// WXF记谱转换为走法, 按当前行棋方解析
+pub fn parse_wxf(board: &Board, text: &str) -> Result<Move, String> {
+    let invalid = || format!("无法识别的着法: {}", text);
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let [first, second, action, target] = chars[..] else {
+        return Err(invalid());
+    };
+    let action = match action {
+        '+' => Action::Advance,
+        '-' => Action::Retreat,
+        '=' | '.' => Action::Traverse,
+        _ => return Err(invalid()),
+    };
+    let target = parse_digit(target).ok_or_else(invalid)?;
+
+    let order = match first {
+        '+' => Some(Order::Front),
+        '-' => Some(Order::Back),
+        _ => parse_digit(first).filter(|&n| n <= 5).map(Order::Nth),
+    };
+    let mover = match (parse_letter(first), order, parse_letter(second), parse_digit(second)) {
+        // 兵种 + 纵线
+        (Some(kind), _, _, Some(file)) => Mover { kind, file: Some(file), order: None },
+        // 前后次序 + 兵种
+        (None, Some(order), Some(kind), _) => Mover { kind, file: None, order: Some(order) },
+        // 前后次序 + 纵线, 只用于兵
+        (None, Some(order), None, Some(file)) => Mover { kind: PieceKind::Pawn, file: Some(file), order: Some(order) },
+        _ => return Err(invalid()),
+    };
+    resolve(board, mover, action, target, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::movegen::legal_moves;
+
+    fn format(fen: &str, iccs: &str) -> String { board_move_wxf(&fen.parse().unwrap(), iccs.parse().unwrap()) }
+
+    fn parse(fen: &str, text: &str) -> Result<String, String> {
+        parse_wxf(&fen.parse().unwrap(), text).map(|mv| mv.to_string())
+    }
+
+    #[test]
+    fn test_board_move_wxf() {
+        let start = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        assert_eq!(format(start, "h2e2"), "C2=5");
+        assert_eq!(format(start, "h0g2"), "H2+3");
+        assert_eq!(format(start, "g0e2"), "E3+5");
+        assert_eq!(format(start, "f0e1"), "A4+5");
+        assert_eq!(format(start, "e0e1"), "K5+1");
+        assert_eq!(format(start, "i0i2"), "R1+2");
+        assert_eq!(format(start, "b2b9"), "C8+7");
+
+        let black = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b";
+        assert_eq!(format(black, "h9g7"), "H8+7");
+        assert_eq!(format(black, "b7e7"), "C2=5");
+        assert_eq!(format(black, "i9i8"), "R9+1");
+
+        let rooks = "4k4/9/9/9/9/9/7R1/9/9/3K3R1 w";
+        assert_eq!(format(rooks, "h3h5"), "+R+2");
+        assert_eq!(format(rooks, "h0h1"), "-R+1");
+        assert_eq!(format(rooks, "h3f3"), "+R=4");
+
+        let pawns = "4k4/9/4P4/4P4/4P4/9/9/9/9/3K5 w";
+        assert_eq!(format(pawns, "e7f7"), "1P=4");
+        assert_eq!(format(pawns, "e6d6"), "2P=6");
+        assert_eq!(format(pawns, "e5f5"), "3P=4");
+
+        let files = "4k4/9/2P1P4/2P1P4/9/9/9/9/9/3K5 w";
+        assert_eq!(format(files, "e7f7"), "+5=4");
+        assert_eq!(format(files, "c6b6"), "-7=8");
+    }
+
+    #[test]
+    fn test_parse_wxf() {
+        let start = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        assert_eq!(parse(start, "C2=5"), Ok("h2e2".to_string()));
+        assert_eq!(parse(start, "c2.5"), Ok("h2e2".to_string()));
+        assert_eq!(parse(start, "N2+3"), Ok("h0g2".to_string()));
+        assert_eq!(parse(start, "B3+5"), Ok("g0e2".to_string()));
+
+        let rooks = "4k4/9/9/9/9/9/7R1/9/9/3K3R1 w";
+        assert_eq!(parse(rooks, "+R=4"), Ok("h3f3".to_string()));
+        assert_eq!(parse(rooks, "-R+1"), Ok("h0h1".to_string()));
+        assert!(parse(rooks, "R2+1").unwrap_err().contains("歧义"));
+
+        let files = "4k4/9/2P1P4/2P1P4/9/9/9/9/9/3K5 w";
+        assert_eq!(parse(files, "+5=4"), Ok("e7f7".to_string()));
+        assert!(parse(files, "+P=4").unwrap_err().contains("歧义"));
+
+        for text in ["", "C2=", "C2=55", "C2*5", "X2=5", "C0=5", "+X=5"] {
+            assert!(parse(start, text).is_err(), "{}", text);
+        }
+        assert!(parse(start, "R1+4").unwrap_err().contains("不合法"));
+        assert!(parse(start, "C5=4").unwrap_err().contains("找不到"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for fen in [
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w",
+            "2rakab2/9/1cn6/p3p3p/2b2n3/6R2/P3P1c1P/2N1C3C/4N4/2BAKAB2 b",
+            "r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w",
+            "4k4/9/9/9/9/9/7R1/9/9/3K3R1 w",
+            "4k4/9/4P4/4P4/4P4/9/9/9/9/3K5 w",
+            "4k4/9/2P1P4/2P1P4/9/9/9/9/9/3K5 w",
+            "3k5/9/9/9/9/9/1c7/1c7/2p1p4/5K3 b",
+        ] {
+            let board: Board = fen.parse().unwrap();
+            for mv in legal_moves(&board) {
+                let text = board_move_wxf(&board, mv);
+                assert_eq!(parse_wxf(&board, &text), Ok(mv), "{} {}", fen, text);
+            }
+        }
+    }
+}
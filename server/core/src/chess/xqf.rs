@@ -0,0 +1,485 @@
+use encoding_rs::GBK;
+
+use super::game::Game;
+use super::game::GameResult;
+use super::game::Node;
+use super::game::MAX_PLIES;
+use super::game::PIECE_ORDER;
+use super::movegen::is_legal_move;
+use super::Board;
+use super::Color;
+use super::Move;
+use super::Piece;
+use super::Square;
+
+// 文件头长度, 着法记录紧随其后
+const HEADER_SIZE: usize = 1024;
+// 不加密的版本号, 11及以上的版本加密
+const PLAIN_VERSION: u8 = 10;
+// 加密流的种子
+const COPYRIGHT: &[u8; 32] = b"[(C) Copyright Mr. Dong Shiwei.]";
+
+// 文件头中字符串的位置和最大长度
+const TITLE: (usize, usize) = (0x50, 63);
+const EVENT: (usize, usize) = (0xD0, 63);
+const DATE: (usize, usize) = (0x110, 15);
+const SITE: (usize, usize) = (0x120, 15);
+const RED: (usize, usize) = (0x130, 15);
+const BLACK: (usize, usize) = (0x140, 15);
+const ANNOTATOR: (usize, usize) = (0x1D0, 15);
+
+// 解密用的钥匙, 不加密的版本全部为0
+#[derive(Debug, Default)]
+struct Keys {
+    xy: u8,
+    xyf: u8,
+    xyt: u8,
+    comment: u32,
+    stream: [u8; 32],
+}
+
+fn cal_key(key: u8, seed: u8) -> u8 {
+    let key = key as u32;
+    ((((((key * key) * 3 + 9) * 3 + 8) * 2 + 1) * 3 + 8) * seed as u32) as u8
+}
+
+impl Keys {
+    fn new(header: &[u8]) -> Self {
+        if header[2] <= PLAIN_VERSION {
+            return Self::default();
+        }
+        let mask = header[3];
+        let [sum, xy, xyf, xyt] = [header[12], header[13], header[14], header[15]];
+        let key_xy = cal_key(xy, xy);
+        let key_xyf = cal_key(xyf, key_xy);
+        let key_xyt = cal_key(xyt, key_xyf);
+        let bytes =
+            [(sum & mask) | header[8], (xy & mask) | header[9], (xyf & mask) | header[10], (xyt & mask) | header[11]];
+        let mut stream = [0; 32];
+        for (i, key) in stream.iter_mut().enumerate() {
+            *key = COPYRIGHT[i] & bytes[i % 4];
+        }
+        Self { xy: key_xy, xyf: key_xyf, xyt: key_xyt, comment: (sum as u32 * 256 + xy as u32) % 32000 + 767, stream }
+    }
+}
+
+// XQF的坐标为 列*10+行, 行从红方底线开始
+fn square(xy: u8) -> Result<Square, String> {
+    match xy {
+        0..=89 => Ok(Square::new(xy as usize / 10, 9 - xy as usize % 10)),
+        _ => Err(format!("XQF坐标无效: {}", xy)),
+    }
+}
+
+fn xy(sq: Square) -> u8 { (sq.x() * 10 + 9 - sq.y()) as u8 }
+
+fn decode(bytes: &[u8]) -> String {
+    let (text, _, _) = GBK.decode(bytes);
+    text.trim_end_matches('\0').to_string()
+}
+
+// 一条着法记录
+struct Record {
+    from: u8,
+    to: u8,
+    next: bool,
+    variation: bool,
+    comment: String,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    version: u8,
+    keys: Keys,
+}
+
+impl Reader<'_> {
+    // 读取并按文件位置解密
+    fn bytes(&mut self, len: usize) -> Result<Vec<u8>, String> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.data.len()).ok_or("XQF文件不完整")?;
+        let bytes = (self.pos..end).map(|i| self.data[i].wrapping_sub(self.keys.stream[i % 32])).collect();
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn record(&mut self) -> Result<Record, String> {
+        let bytes = self.bytes(4)?;
+        let (next, variation, comment) = if self.version <= PLAIN_VERSION {
+            (bytes[2] & 0xF0 != 0, bytes[2] & 0x0F != 0, true)
+        } else {
+            (bytes[2] & 0x80 != 0, bytes[2] & 0x40 != 0, bytes[2] & 0x20 != 0)
+        };
+        let comment = if comment {
+            let len = self.u32()?.wrapping_sub(self.keys.comment);
+            decode(&self.bytes(len as usize)?)
+        } else {
+            String::new()
+        };
+        Ok(Record {
+            from: bytes[0].wrapping_sub(0x18).wrapping_sub(self.keys.xyf),
+            to: bytes[1].wrapping_sub(0x20).wrapping_sub(self.keys.xyt),
+            next,
+            variation,
+            comment,
+        })
+    }
+
+    // 读取一组同级的着法, 每个着法之后先是它的后续, 再是它的变着
+    fn siblings(&mut self, board: &Board, depth: usize) -> Result<Vec<Node>, String> {
+        if depth >= MAX_PLIES {
+            return Err(format!("XQF棋谱超过{}步", MAX_PLIES));
+        }
+        let mut board = *board;
+        let mut nodes = Vec::new();
+        loop {
+            let record = self.record()?;
+            let mv = Move::new(square(record.from)?, square(record.to)?);
+            let piece = board.get(mv.from).ok_or_else(|| format!("XQF着法没有棋子: {}", mv))?;
+            // 起始局面不记录行棋方, 由第一步决定, 之后双方轮流走
+            if depth == 0 && nodes.is_empty() {
+                board = board.with_turn(piece.color);
+            }
+            if !is_legal_move(&board, mv) {
+                return Err(format!("XQF着法不合法: {}", mv));
+            }
+            let children = if record.next { self.siblings(&board.make_move(mv), depth + 1)? } else { Vec::new() };
+            nodes.push(Node { mv: Some(mv), comment: record.comment, children });
+            if !record.variation {
+                return Ok(nodes);
+            }
+        }
+    }
+}
+
+// 读取XQF棋谱, 支持加密的版本
+pub fn read_xqf(data: &[u8]) -> Result<Game, String> {
+    if data.len() < HEADER_SIZE || &data[..2] != b"XQ" {
+        return Err("不是XQF文件".to_string());
+    }
+    let header = &data[..HEADER_SIZE];
+    let version = header[2];
+    let keys = Keys::new(header);
+
+    let mut game = Game::default();
+    for (i, &stored) in header[0x10..0x30].iter().enumerate() {
+        // 12版以后棋子顺序也被打乱
+        let index = if version >= 12 { (keys.xy as usize + 1 + i) % 32 } else { i };
+        let pos = stored.wrapping_sub(keys.xy);
+        if pos < 90 {
+            game.start.set(square(pos)?, Piece::from_char(PIECE_ORDER[index] as char));
+        }
+    }
+    game.start.check_counts()?;
+    game.result = match header[0x33] {
+        1 => GameResult::RedWin,
+        2 => GameResult::BlackWin,
+        3 => GameResult::Draw,
+        _ => GameResult::Unknown,
+    };
+    let string =
+        |(offset, cap): (usize, usize)| decode(&header[offset + 1..offset + 1 + (header[offset] as usize).min(cap)]);
+    game.title = string(TITLE);
+    game.event = string(EVENT);
+    game.date = string(DATE);
+    game.site = string(SITE);
+    game.red = string(RED);
+    game.black = string(BLACK);
+    game.annotator = string(ANNOTATOR);
+
+    // 第一条记录是根节点, 只有注释
+    let mut reader = Reader { data, pos: HEADER_SIZE, version, keys };
+    let root = reader.record()?;
+    game.root.comment = root.comment;
+    if root.next {
+        game.root.children = reader.siblings(&game.start, 0)?;
+    }
+    game.start.turn = game
+        .root
+        .children
+        .first()
+        .and_then(|node| game.start.get(node.mv?.from))
+        .map_or(Color::Red, |piece| piece.color);
+    Ok(game)
+}
+
+struct Writer {
+    data: Vec<u8>,
+    version: u8,
+    keys: Keys,
+}
+
+impl Writer {
+    // 按文件位置加密后写入
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            let key = self.keys.stream[self.data.len() % 32];
+            self.data.push(byte.wrapping_add(key));
+        }
+    }
+
+    fn record(&mut self, mv: Option<Move>, next: bool, variation: bool, comment: &str) {
+        let (from, to) = mv.map_or((0, 0), |mv| (xy(mv.from), xy(mv.to)));
+        let comment = GBK.encode(comment).0;
+        let flag = |set: bool, bits: u8| if set { bits } else { 0 };
+        let tag = if self.version <= PLAIN_VERSION {
+            flag(next, 0xF0) | flag(variation, 0x0F)
+        } else {
+            flag(next, 0x80) | flag(variation, 0x40) | flag(!comment.is_empty(), 0x20)
+        };
+        self.bytes(&[
+            from.wrapping_add(0x18).wrapping_add(self.keys.xyf),
+            to.wrapping_add(0x20).wrapping_add(self.keys.xyt),
+            tag,
+            0,
+        ]);
+        if self.version <= PLAIN_VERSION || !comment.is_empty() {
+            self.bytes(&(comment.len() as u32).wrapping_add(self.keys.comment).to_le_bytes());
+            self.bytes(&comment);
+        }
+    }
+
+    fn siblings(&mut self, nodes: &[Node]) -> Result<(), String> {
+        for (i, node) in nodes.iter().enumerate() {
+            let mv = node.mv.ok_or("棋谱节点缺少着法")?;
+            self.record(Some(mv), !node.children.is_empty(), i + 1 < nodes.len(), &node.comment);
+            self.siblings(&node.children)?;
+        }
+        Ok(())
+    }
+}
+
+// 按给定的版本和文件头钥匙写入, 加密的版本只用于测试读取
+fn write(game: &Game, header: &[u8; 16]) -> Result<Vec<u8>, String> {
+    let mut data = vec![0; HEADER_SIZE];
+    data[..16].copy_from_slice(header);
+    let version = header[2];
+    let keys = Keys::new(&data);
+
+    let mut slots = [0xFF; 32];
+    for (sq, piece) in game.start.pieces() {
+        let slot = (0..32)
+            .find(|&i| PIECE_ORDER[i] as char == piece.to_char() && slots[i] == 0xFF)
+            .ok_or_else(|| format!("棋子数量超出XQF的限制: {}", piece.to_char()))?;
+        slots[slot] = xy(sq);
+    }
+    for i in 0..32 {
+        let index = if version >= 12 { (keys.xy as usize + 1 + i) % 32 } else { i };
+        data[0x10 + i] = slots[index].wrapping_add(keys.xy);
+    }
+    data[0x33] = match game.result {
+        GameResult::Unknown => 0,
+        GameResult::RedWin => 1,
+        GameResult::BlackWin => 2,
+        GameResult::Draw => 3,
+    };
+    for ((offset, cap), text) in [
+        (TITLE, &game.title),
+        (EVENT, &game.event),
+        (DATE, &game.date),
+        (SITE, &game.site),
+        (RED, &game.red),
+        (BLACK, &game.black),
+        (ANNOTATOR, &game.annotator),
+    ] {
+        // 超长时按字符截断, 避免截断半个汉字
+        let mut text = text.as_str();
+        while GBK.encode(text).0.len() > cap {
+            text = &text[..text.char_indices().last().map_or(0, |(i, _)| i)];
+        }
+        let bytes = GBK.encode(text).0;
+        data[offset] = bytes.len() as u8;
+        data[offset + 1..offset + 1 + bytes.len()].copy_from_slice(&bytes);
+    }
+
+    let mut writer = Writer { data, version, keys };
+    writer.record(None, !game.root.children.is_empty(), false, &game.root.comment);
+    writer.siblings(&game.root.children)?;
+    Ok(writer.data)
+}
+
+// 写入不加密的XQF棋谱
+pub fn write_xqf(game: &Game) -> Result<Vec<u8>, String> {
+    let mut header = [0; 16];
+    header[..3].copy_from_slice(&[b'X', b'Q', PLAIN_VERSION]);
+    write(game, &header)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // 开局时32个棋子的XQF坐标
+    const START: [u8; 32] = [
+        0, 10, 20, 30, 40, 50, 60, 70, 80, 12, 72, 3, 23, 43, 63, 83, //
+        9, 19, 29, 39, 49, 59, 69, 79, 89, 17, 77, 6, 26, 46, 66, 86,
+    ];
+
+    fn mv(text: &str) -> Move { text.parse().unwrap() }
+
+    fn node(text: &str, comment: &str, children: Vec<Node>) -> Node {
+        Node { mv: Some(mv(text)), comment: comment.to_string(), children }
+    }
+
+    fn sample() -> Game {
+        let mut game = Game::new(Board::startpos());
+        game.title = "测试对局".to_string();
+        game.event = "全国象棋个人赛".to_string();
+        game.date = "2024.12.31".to_string();
+        game.site = "北京".to_string();
+        game.red = "红方棋手".to_string();
+        game.black = "黑方棋手".to_string();
+        game.annotator = "xqlink".to_string();
+        game.result = GameResult::RedWin;
+        game.root.comment = "中炮对屏风马".to_string();
+        game.root.children = vec![
+            node(
+                "h2e2",
+                "当头炮",
+                vec![
+                    node("h9g7", "", vec![node("h0g2", "", vec![]), node("b0c2", "变着", vec![])]),
+                    node("b7e7", "顺炮", vec![]),
+                ],
+            ),
+            node("c3c4", "仙人指路", vec![]),
+        ];
+        game
+    }
+
+    #[test]
+    fn test_read_plain() {
+        // 按格式手工构造: 根节点带注释, 一步炮二平五
+        let mut data = vec![0; HEADER_SIZE];
+        data[..3].copy_from_slice(b"XQ\x0A");
+        data[0x10..0x30].copy_from_slice(&START);
+        data[0x130] = 4;
+        data[0x131..0x135].copy_from_slice(&GBK.encode("许银").0);
+        data.extend([0x18, 0x20, 0xF0, 0]);
+        let comment = GBK.encode("开局").0;
+        data.extend((comment.len() as u32).to_le_bytes());
+        data.extend(comment.iter());
+        data.extend([72 + 0x18, 42 + 0x20, 0, 0, 0, 0, 0, 0]);
+
+        let game = read_xqf(&data).unwrap();
+        assert!(game.start.same_placement(&Board::startpos()));
+        assert_eq!(game.start.turn, Color::Red);
+        assert_eq!(game.red, "许银");
+        assert_eq!(game.root.comment, "开局");
+        assert_eq!(game.main_line(), vec![mv("h2e2")]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let game = sample();
+        let data = write_xqf(&game).unwrap();
+        assert_eq!(&data[0x10..0x30], &START);
+        assert_eq!(read_xqf(&data).unwrap(), game);
+        assert_eq!(game.main_line(), vec![mv("h2e2"), mv("h9g7"), mv("h0g2")]);
+
+        // 黑方先行的残局
+        let mut game = Game::new("3k5/9/9/9/9/9/9/9/4r4/3K5 b".parse().unwrap());
+        game.root.children = vec![node("e1d1", "", vec![node("d0e0", "", vec![])])];
+        let read = read_xqf(&write_xqf(&game).unwrap()).unwrap();
+        assert_eq!(read.start.turn, Color::Black);
+        assert_eq!(read, game);
+    }
+
+    #[test]
+    fn test_encrypted() {
+        let game = sample();
+        for version in [11, 12, 18] {
+            let header = [b'X', b'Q', version, 0x5A, 1, 2, 3, 4, 0x13, 0x27, 0x4B, 0x8D, 0x61, 0x9C, 0x3E, 0xD2];
+            let data = write(&game, &header).unwrap();
+            assert_ne!(&data[0x10..0x30], &START);
+            assert_eq!(read_xqf(&data).unwrap(), game, "version {}", version);
+        }
+    }
+
+    // 按XQF格式说明独立算出的钥匙, 钥匙 = (x*x*54+221)*种子, 不经过cal_key
+    #[test]
+    fn test_keys() {
+        let mut header = [0; 16];
+        header[..4].copy_from_slice(&[b'X', b'Q', 0x12, 0x5D]);
+        header[8..].copy_from_slice(&[0x11, 0x22, 0x33, 0x44, 0x9C, 0x27, 0xE3, 0x61]);
+        let keys = Keys::new(&header);
+        assert_eq!((keys.xy, keys.xyf, keys.xyt, keys.comment), (0x45, 0x0F, 0x9D, 8742));
+        assert_eq!(keys.stream[..8], [25, 32, 67, 1, 0, 3, 99, 64]);
+        assert_eq!(keys.stream[28..], [5, 33, 34, 69]);
+    }
+
+    // 其他软件保存的XQF棋谱, 每个 .xqf 文件对应一个同名的 .txt, 第一行为起始局面FEN, 第二行为主线着法(ICCS)
+    // 仓库中没有附带棋谱, 需要用环境变量 XQLINK_XQF_DIR 指定目录后以 --ignored 运行, 目录中没有棋谱时失败
+    #[test]
+    #[ignore = "需要设置 XQLINK_XQF_DIR 为存放XQF棋谱的目录"]
+    fn test_fixtures() {
+        let dir = env::var("XQLINK_XQF_DIR")
+            .map_or_else(|_| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/xqf"), PathBuf::from);
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+            .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        files.retain(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("xqf")));
+        files.sort();
+        assert!(!files.is_empty(), "{} 中没有XQF棋谱, 请设置 XQLINK_XQF_DIR", dir.display());
+        for path in files {
+            let expected = fs::read_to_string(path.with_extension("txt")).unwrap();
+            let mut lines = expected.lines();
+            let start: Board = lines.next().unwrap().parse().unwrap();
+            let moves: Vec<Move> = lines.next().unwrap_or_default().split_whitespace().map(mv).collect();
+            let game = read_xqf(&fs::read(&path).unwrap()).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            assert!(game.start.same_placement(&start), "{}", path.display());
+            assert_eq!(game.main_line(), moves, "{}", path.display());
+        }
+    }
+
+    // 双方必须轮流走, 只有第一步可以决定行棋方
+    #[test]
+    fn test_turn() {
+        let mut game = Game::new(Board::startpos());
+        game.root.children = vec![node("h2e2", "", vec![node("h0g2", "", vec![])])];
+        assert!(read_xqf(&write_xqf(&game).unwrap()).unwrap_err().contains("不合法"));
+        // 第一步的变着也要由同一方走
+        game.root.children = vec![node("h2e2", "", vec![]), node("h9g7", "", vec![])];
+        assert!(read_xqf(&write_xqf(&game).unwrap()).unwrap_err().contains("不合法"));
+    }
+
+    // 马来回跳动的长棋谱
+    fn long_game(plies: usize) -> Game {
+        let moves = ["h0g2", "h9g7", "g2h0", "g7h9"];
+        let mut game = Game::new(Board::startpos());
+        game.root.children = (0..plies).rev().fold(Vec::new(), |children, i| vec![node(moves[i % 4], "", children)]);
+        game
+    }
+
+    #[test]
+    fn test_max_plies() {
+        let game = long_game(MAX_PLIES);
+        assert_eq!(read_xqf(&write_xqf(&game).unwrap()).unwrap(), game);
+        let data = write_xqf(&long_game(MAX_PLIES + 1)).unwrap();
+        assert!(read_xqf(&data).unwrap_err().contains("超过"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(read_xqf(b"XQ").is_err());
+        let data = write_xqf(&sample()).unwrap();
+        assert!(read_xqf(&data[..data.len() - 3]).unwrap_err().contains("不完整"));
+        let mut bad = data.clone();
+        bad[0] = b'Q';
+        assert!(read_xqf(&bad).is_err());
+        // 篡改第一步的起点
+        let mut bad = data.clone();
+        let first = HEADER_SIZE + 8 + GBK.encode("中炮对屏风马").0.len();
+        bad[first] = 0x18 + 44;
+        assert!(read_xqf(&bad).unwrap_err().contains("XQF着法"));
+    }
+}
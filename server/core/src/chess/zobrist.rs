@@ -0,0 +1,105 @@
+use super::Board;
+use super::Piece;
+use super::Square;
+
+// 固定种子, 修改后所有已保存的局面键都会失效
+const SEED: u64 = 0x5851_4C49_4E4B_0001;
+
+// splitmix64, 返回(新状态, 随机数)
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+// 编译期生成 14种棋子 x 90个位置 的键, 以及行棋方的键
+const fn generate() -> ([[u64; 90]; 14], u64) {
+    let mut keys = [[0; 90]; 14];
+    let mut state = SEED;
+    let mut i = 0;
+    while i < 14 {
+        let mut j = 0;
+        while j < 90 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys[i][j] = key;
+            j += 1;
+        }
+        i += 1;
+    }
+    let (_, side) = splitmix64(state);
+    (keys, side)
+}
+
+const KEYS: ([[u64; 90]; 14], u64) = generate();
+
+// 黑方行棋时异或该键
+pub const SIDE_KEY: u64 = KEYS.1;
+
+pub fn piece_key(piece: Piece, sq: Square) -> u64 { KEYS.0[piece.color as usize * 7 + piece.kind as usize][sq.index()] }
+
+// 从头计算棋子摆放的键, 用于旋转后重建和校验增量结果
+pub fn placement_key(board: &Board) -> u64 { board.pieces().fold(0, |key, (sq, piece)| key ^ piece_key(piece, sq)) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::movegen::legal_moves;
+    use crate::chess::Color;
+
+    fn full_key(board: &Board) -> u64 {
+        match board.turn {
+            Color::Red => placement_key(board),
+            Color::Black => placement_key(board) ^ SIDE_KEY,
+        }
+    }
+
+    #[test]
+    fn test_stable_key() {
+        // 键由固定种子生成, 变化意味着已保存的局面键全部失效
+        assert_eq!(Board::startpos().zobrist(), 0x842F5B60D64F44C9);
+        assert_eq!(Board::default().zobrist(), 0);
+    }
+
+    #[test]
+    fn test_incremental() {
+        // 每步选取不同下标的走法, 覆盖吃子和双方行棋
+        let mut board = Board::startpos();
+        for i in 0..80 {
+            let moves = legal_moves(&board);
+            if moves.is_empty() {
+                break;
+            }
+            board = board.make_move(moves[(i * 7) % moves.len()]);
+            assert_eq!(board.zobrist(), full_key(&board), "{}", board);
+            assert_eq!(board.to_string().parse::<Board>().unwrap().zobrist(), board.zobrist());
+        }
+        let mut rotated = board;
+        rotated.rotate();
+        assert_eq!(rotated.zobrist(), full_key(&rotated));
+        rotated.rotate();
+        assert_eq!(rotated.zobrist(), board.zobrist());
+    }
+
+    #[test]
+    fn test_transposition() {
+        let play =
+            |moves: &[&str]| moves.iter().fold(Board::startpos(), |board, mv| board.make_move(mv.parse().unwrap()));
+        let a = play(&["h2e2", "h9g7", "h0g2", "b9c7"]);
+        let b = play(&["h0g2", "b9c7", "h2e2", "h9g7"]);
+        assert_eq!(a.zobrist(), b.zobrist());
+        // 计数不影响键
+        assert_ne!((a.halfmove, a.fullmove), (0, 1));
+        assert_eq!(a.zobrist(), a.to_string().split(' ').next().unwrap().parse::<Board>().unwrap().zobrist());
+
+        // 行棋方不同, 键不同
+        let board = Board::startpos();
+        assert_ne!(board.zobrist(), board.with_turn(Color::Black).zobrist());
+        assert_eq!(board.zobrist() ^ SIDE_KEY, board.with_turn(Color::Black).zobrist());
+        // 回到原来的摆放, 键也回到原值
+        let back = play(&["h2e2", "h9g7", "e2h2", "g7h9"]);
+        assert_eq!(back.zobrist(), board.zobrist());
+    }
+}
@@ -45,9 +45,9 @@ const MODEL_CELL_W: f32 = yolo::IMAGE_WIDTH as f32 / 9.0;
 const MODEL_CELL_H: f32 = yolo::IMAGE_HEIGHT as f32 / 10.0;
 
 // detections_to_board 识别结果转换为棋盘结构
-pub fn detections_to_board(detections: &[yolo::Detection]) -> Result<(chess::Camp, [[char; 9]; 10]), String> {
+pub fn detections_to_board(detections: &[yolo::Detection]) -> Result<(chess::Camp, chess::Board), String> {
     let mut camp = chess::Camp::None;
-    let mut board = [[' '; 9]; 10];
+    let mut board = chess::Board::default();
 
     match detections.iter().find(|&&x| x.label == '0') {
         Some(_) => {
@@ -66,7 +66,7 @@ pub fn detections_to_board(detections: &[yolo::Detection]) -> Result<(chess::Cam
                 }
 
                 // 构建board
-                board[row][col] = det.label;
+                board.set(chess::Square::new(col, row), chess::Piece::from_char(det.label));
 
                 // 判断阵营
                 if camp == chess::Camp::None && (3..=5).contains(&col) && row >= 7 {
@@ -80,5 +80,7 @@ pub fn detections_to_board(detections: &[yolo::Detection]) -> Result<(chess::Cam
         }
         None => return Err("not board".to_string()),
     }
+    // 默认由我方行棋
+    board.turn = camp.color();
     Ok((camp, board))
 }
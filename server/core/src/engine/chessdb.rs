@@ -1,5 +1,9 @@
 use std::time::Duration;
 
+use tracing::trace;
+use tracing::warn;
+
+use super::Candidate;
 use super::QueryResult;
 use super::QueryState;
 
@@ -23,9 +27,11 @@ pub async fn query(fen: &str, timeout: u64) -> QueryResult {
             let text = text.strip_suffix('\0').unwrap();
             match text {
                 "" | "unknown" => records.state = QueryState::NotResult,
-                "invalid board" | "checkmate" | "stalemate" => records.state = QueryState::InvalidBoard,
+                "invalid board" => records.state = QueryState::InvalidBoard,
+                "checkmate" => records.state = QueryState::Checkmate,
+                "stalemate" => records.state = QueryState::Stalemate,
                 text => {
-                    println!("{}", text);
+                    trace!("{}", text);
                     for pair in text.split(',') {
                         let mut parts = pair.split(':');
                         match parts.next().unwrap_or("") {
@@ -40,13 +46,20 @@ pub async fn query(fen: &str, timeout: u64) -> QueryResult {
                             _ => {}
                         }
                     }
+                    // 云库只返回最佳着法的思考
+                    records.candidates.push(Candidate {
+                        depth: records.depth,
+                        score: records.score,
+                        pvs: records.pvs.clone(),
+                        ..Default::default()
+                    });
                     records.state = QueryState::Success;
                     records.source = SOURCE_CHESSDB.to_string();
                 }
             }
         }
         Err(e) => {
-            println!("{}", e);
+            warn!("{}", e);
             records.state = QueryState::ServerInternalError
         }
     };
@@ -0,0 +1,617 @@
+pub mod chessdb;
+use std::fmt::Display;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+mod command;
+
+use tokio::sync::mpsc;
+pub use tokio_util::sync::CancellationToken;
+use tracing::debug;
+use tracing::trace;
+
+#[derive(Debug, serde::Serialize, Default, Clone)]
+pub struct QueryResult {
+    pub depth: usize,               // 深度
+    pub score: isize,               // 得分
+    pub time: usize,                // 时间
+    pub pvs: Vec<String>,           // 思考(iccs)
+    pub moves: Vec<String>,         // 思考(chinese)
+    pub wxf: Vec<String>,           // 思考(wxf)
+    pub warning: Option<String>,    // 循环违例提醒
+    pub candidates: Vec<Candidate>, // 候选着法(MultiPV), 第一个即最佳着法
+    pub state: QueryState,          // 状态
+    pub source: String,             // 来源
+}
+
+// MultiPV中的一条候选变化
+#[derive(Debug, serde::Serialize, Default, Clone)]
+pub struct Candidate {
+    pub depth: usize,       // 深度
+    pub score: isize,       // 得分
+    pub pvs: Vec<String>,   // 思考(iccs)
+    pub moves: Vec<String>, // 思考(chinese)
+    pub wxf: Vec<String>,   // 思考(wxf)
+}
+
+// 引擎搜索中每完成一层的思考
+#[derive(Debug, serde::Serialize, Default, Clone)]
+pub struct Progress {
+    pub multipv: usize,     // 候选序号, 从1开始
+    pub depth: usize,       // 深度
+    pub seldepth: usize,    // 选择性深度
+    pub score: isize,       // 得分
+    pub nodes: u64,         // 节点数
+    pub nps: u64,           // 每秒节点数
+    pub time: usize,        // 时间
+    pub pvs: Vec<String>,   // 思考(iccs)
+    pub moves: Vec<String>, // 思考(chinese), 由调用方翻译
+}
+
+impl Progress {
+    // 解析带有pv的info输出, 上下界(lowerbound/upperbound)的中间结果不算完成的一层
+    pub fn parse(line: &str) -> Option<Self> {
+        fn value<T: FromStr>(token: Option<&str>) -> Option<T> { token?.parse().ok() }
+
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("info") {
+            return None;
+        }
+        let mut progress = Progress { multipv: 1, ..Default::default() };
+        while let Some(key) = tokens.next() {
+            match key {
+                "multipv" => progress.multipv = value(tokens.next())?,
+                "depth" => progress.depth = value(tokens.next())?,
+                "seldepth" => progress.seldepth = value(tokens.next())?,
+                "nodes" => progress.nodes = value(tokens.next())?,
+                "nps" => progress.nps = value(tokens.next())?,
+                "time" => progress.time = value(tokens.next())?,
+                "score" => {
+                    progress.score = match tokens.next()? {
+                        "cp" => value(tokens.next())?,
+                        "mate" => {
+                            let round: isize = value(tokens.next())?;
+                            if round > 0 {
+                                30000 - round
+                            } else {
+                                -(30000 + round)
+                            }
+                        }
+                        _ => return None,
+                    }
+                }
+                "lowerbound" | "upperbound" => return None,
+                "pv" => {
+                    progress.pvs = tokens.map(String::from).collect();
+                    break;
+                }
+                _ => {}
+            }
+        }
+        (!progress.pvs.is_empty()).then_some(progress)
+    }
+}
+
+impl From<Progress> for Candidate {
+    fn from(progress: Progress) -> Self {
+        Self { depth: progress.depth, score: progress.score, pvs: progress.pvs, ..Default::default() }
+    }
+}
+
+const SOURCE_ENGINE: &str = "引擎";
+
+#[derive(Debug, serde::Serialize, Default, Clone, Copy)]
+pub enum QueryState {
+    Success,
+    #[default]
+    NotResult,
+    InvalidBoard,
+    Checkmate,           // 被将死
+    Stalemate,           // 困毙
+    ServerInternalError, // 内部错误
+}
+
+#[derive(Debug, serde::Serialize, Clone, serde::Deserialize, Copy)]
+#[serde(default)]
+pub struct EngineConfig {
+    pub depth: usize,
+    pub time: usize,
+    pub threads: usize,
+    pub hash: usize,
+    pub show_wdl: bool,
+    pub chessdb_enabled: bool,
+    pub chessdb_timeout: u64,
+    pub multipv: usize, // 候选着法数量
+    pub infinite: bool, // 无限分析, 不查询云库, 直到取消才停止
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            depth: 20,
+            time: 5000,
+            threads: 4,
+            hash: 64,
+            show_wdl: false,
+            chessdb_enabled: true,
+            chessdb_timeout: 5,
+            multipv: 1,
+            infinite: false,
+        }
+    }
+}
+
+pub struct Engine {
+    stdin: Box<dyn Write>,
+    lines: mpsc::UnboundedReceiver<String>, // 后台线程读取的引擎输出
+    child: std::process::Child,             // 添加子进程字段
+    libs: PathBuf,                          // 引擎所在目录, 重启时使用
+    options: Vec<(String, String)>,         // 已设置的选项, 重启后重新设置
+}
+
+unsafe impl Send for Engine {}
+unsafe impl Sync for Engine {}
+
+impl Engine {
+    pub fn new(libs: &Path) -> Self {
+        let mut child = command::new(libs);
+
+        let nnue = libs.join("pikafish.nnue");
+
+        let stdin = Box::new(child.stdin.take().unwrap());
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let (sender, lines) = mpsc::unbounded_channel();
+        // 后台线程持续读取引擎输出, 引擎退出或Engine销毁时结束
+        thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                trace!("line::{}", line);
+                if sender.send(line.trim().to_string()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut eng = Engine { stdin, lines, child, libs: libs.to_path_buf(), options: Vec::new() };
+        eng.setoption("EvalFile", nnue.display());
+        eng.setoption("Sixty Move Rule", false);
+        eng
+    }
+
+    pub fn reload(&mut self, libs: &Path, config: &EngineConfig) {
+        self.child.kill().unwrap();
+        self.child.wait().unwrap();
+        *self = Self::new(libs);
+        self.set_hash(config.hash);
+        self.set_show_wdl(config.show_wdl);
+        self.set_threads(config.threads);
+    }
+
+    fn write_command<A: Display>(&mut self, args: A) {
+        writeln!(self.stdin, "{}", args).expect("write command error");
+        self.stdin.flush().expect("write command flush error");
+        debug!("{}", args);
+    }
+
+    pub fn set_show_wdl(&mut self, open: bool) { self.setoption("UCI_ShowWDL", open); }
+
+    pub fn set_threads(&mut self, num: usize) { self.setoption("Threads", num); }
+
+    pub fn set_hash(&mut self, size: usize) { self.setoption("Hash", size); }
+
+    pub fn set_multipv(&mut self, num: usize) { self.setoption("MultiPV", num.max(1)); }
+
+    pub fn setoption<T: Display>(&mut self, name: &str, value: T) {
+        let value = value.to_string();
+        self.write_command(format!("setoption name {} value {}", name, value));
+        match self.options.iter_mut().find(|(option, _)| option == name) {
+            Some(option) => option.1 = value,
+            None => self.options.push((name.to_string(), value)),
+        }
+    }
+
+    // 结束卡住的引擎进程, 重新启动并恢复已设置的选项
+    fn restart(&mut self) {
+        let options = std::mem::take(&mut self.options);
+        *self = Self::new(&self.libs.clone());
+        for (name, value) in options {
+            self.setoption(&name, value);
+        }
+    }
+
+    pub fn position(&mut self, fen: &str) { self.write_command(format!("position fen {}", fen)) }
+
+    // 中止当前的搜索, 引擎会尽快给出bestmove
+    pub fn stop(&mut self) { self.write_command("stop") }
+
+    // 返回每条候选变化最后一层的思考, 按multipv序号排列, 每完成一层通过on_progress通知
+    // 取消时发送stop并等到bestmove, 保证下一次搜索不会读到这次的输出, 返回None
+    async fn bestmove<F: FnMut(&Progress)>(
+        &mut self, depth: usize, time: usize, cancel: &CancellationToken, on_progress: F,
+    ) -> Option<Vec<Progress>> {
+        let command = format!("go depth {} movetime {}", depth, time);
+        let (latest, stopped) = self.go(command, None, cancel, on_progress).await?;
+        (!stopped).then_some(latest)
+    }
+
+    // 无限分析, 引擎不会主动停止, 取消后返回停止前的思考
+    async fn infinite<F: FnMut(&Progress)>(
+        &mut self, cancel: &CancellationToken, on_progress: F,
+    ) -> Option<Vec<Progress>> {
+        let (latest, _) = self.go("go infinite", None, cancel, on_progress).await?;
+        Some(latest)
+    }
+
+    // 发送go命令并读取输出直到bestmove, 返回思考结果以及是否被取消
+    // pondering不为空时是后台思考, 触发后发送ponderhit, 之前的思考不通过on_progress通知
+    async fn go<A: Display, F: FnMut(&Progress)>(
+        &mut self, command: A, mut pondering: Option<&CancellationToken>, cancel: &CancellationToken,
+        mut on_progress: F,
+    ) -> Option<(Vec<Progress>, bool)> {
+        self.write_command(command);
+        let mut latest = Vec::new();
+        let mut stopped = false;
+        loop {
+            let line = tokio::select! {
+                line = self.lines.recv() => line?,
+                _ = cancel.cancelled(), if !stopped => {
+                    self.stop();
+                    stopped = true;
+                    continue;
+                }
+                _ = async move { pondering.unwrap().cancelled().await }, if pondering.is_some() && !stopped => {
+                    self.write_command("ponderhit");
+                    pondering = None;
+                    continue;
+                }
+            };
+            if line.starts_with("bestmove") {
+                trace!("{:?}", latest);
+                break;
+            }
+            if let Some(progress) = Progress::parse(&line) {
+                if !stopped && pondering.is_none() {
+                    on_progress(&progress);
+                }
+                update_latest(&mut latest, progress);
+            }
+        }
+        latest.retain(|progress: &Progress| !progress.pvs.is_empty());
+        Some((latest, stopped))
+    }
+
+    // 调用引擎的 go perft, 返回按ICCS排序的根节点走法拆分结果
+    // perft期间引擎不响应stop, 超时后重启引擎, 避免这次的输出混入后续命令
+    pub async fn perft(&mut self, fen: &str, depth: usize, timeout: Duration) -> Result<Vec<(String, u64)>, String> {
+        self.position(fen);
+        self.write_command(format!("go perft {}", depth));
+        let mut result = Vec::new();
+        let read = async {
+            loop {
+                let line = self.lines.recv().await.ok_or("引擎已退出")?;
+                if line.starts_with("Nodes searched") || line.starts_with("bestmove") {
+                    return Ok::<_, String>(());
+                }
+                if let Some((mv, nodes)) = line.split_once(": ") {
+                    if let Ok(nodes) = nodes.parse() {
+                        result.push((mv.to_string(), nodes));
+                    }
+                }
+            }
+        };
+        match tokio::time::timeout(timeout, read).await {
+            Ok(read) => read?,
+            Err(_) => {
+                self.restart();
+                return Err("引擎perft超时".to_string());
+            }
+        }
+        result.sort();
+        Ok(result)
+    }
+
+    pub async fn search(&mut self, fen: &str, params: &EngineConfig) -> Option<QueryResult> {
+        self.search_with_progress(fen, params, &CancellationToken::new(), |_| {}).await
+    }
+
+    // 与search相同, 调用引擎时每完成一层的思考通过on_progress通知, cancel取消后返回None
+    // 无限分析时cancel是正常的结束方式, 返回取消前的思考
+    pub async fn search_with_progress<F: FnMut(&Progress)>(
+        &mut self, fen: &str, params: &EngineConfig, cancel: &CancellationToken, on_progress: F,
+    ) -> Option<QueryResult> {
+        // 云库只给出一个着法, 需要多条候选时直接调用引擎
+        let result = if params.chessdb_enabled && !params.infinite && params.multipv <= 1 {
+            // 先查询云库
+            tokio::select! {
+                result = chessdb::query(fen, params.chessdb_timeout) => result,
+                _ = cancel.cancelled() => return None,
+            }
+        } else {
+            QueryResult::default()
+        };
+
+        match result.state {
+            QueryState::Success => Some(result),
+            QueryState::InvalidBoard | QueryState::Checkmate | QueryState::Stalemate => None,
+            QueryState::ServerInternalError | QueryState::NotResult => {
+                // 查询云库失败调用引擎
+                self.position(fen);
+                self.set_multipv(params.multipv);
+                let latest = if params.infinite {
+                    self.infinite(cancel, on_progress).await?
+                } else {
+                    self.bestmove(params.depth, params.time, cancel, on_progress).await?
+                };
+                Some(engine_result(result, latest))
+            }
+        }
+    }
+
+    // 对方思考时在后台思考预期的局面(go ponder), 只使用引擎
+    // fen为对局的起始局面, moves为之后的全部着法(ICCS), 最后一步是对方的预期应着, 引擎据此判断循环局面
+    // hit触发表示对方走出了预期的应着, 发送ponderhit转为正常搜索, cancel取消后返回None
+    pub async fn ponder<F: FnMut(&Progress)>(
+        &mut self, fen: &str, moves: &[String], params: &EngineConfig, hit: &CancellationToken,
+        cancel: &CancellationToken, on_progress: F,
+    ) -> Option<QueryResult> {
+        self.write_command(format!("position fen {} moves {}", fen, moves.join(" ")));
+        self.set_multipv(params.multipv);
+        let command = format!("go ponder depth {} movetime {}", params.depth, params.time);
+        let (latest, stopped) = self.go(command, Some(hit), cancel, on_progress).await?;
+        (!stopped).then(|| engine_result(QueryResult::default(), latest))
+    }
+}
+
+// 用引擎的思考填充查询结果
+fn engine_result(mut result: QueryResult, latest: Vec<Progress>) -> QueryResult {
+    if let Some(best) = latest.first() {
+        result.depth = best.depth;
+        result.score = best.score;
+        result.time = best.time;
+        result.pvs = best.pvs.clone();
+    }
+    result.source = SOURCE_ENGINE.to_string();
+    result.candidates = latest.into_iter().map(Candidate::from).collect();
+    result
+}
+
+// 同一multipv序号只保留最新一层的思考
+fn update_latest(latest: &mut Vec<Progress>, progress: Progress) {
+    let index = progress.multipv.max(1) - 1;
+    if latest.len() <= index {
+        latest.resize(index + 1, Progress::default());
+    }
+    latest[index] = progress;
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.write_command("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path;
+
+    use tracing::info;
+    use tracing::Level;
+
+    use super::*;
+    use crate::chess;
+
+    fn init_tracer() { let _ = tracing_subscriber::fmt().with_max_level(Level::TRACE).try_init(); }
+
+    #[tokio::test]
+    async fn test_query() {
+        init_tracer();
+        let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b";
+        let result = chessdb::query(fen, 10).await;
+        info!("{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_engine() {
+        init_tracer();
+        let fen = "4k4/9/6r2/9/9/9/9/9/4A4/4K4 w";
+        let libs = path::PathBuf::from("/Users/atopx/script/chessboard/libs");
+        let mut eng = Engine::new(&libs);
+        let cfg = EngineConfig { chessdb_enabled: false, ..Default::default() };
+        let records = eng.search(fen, &cfg).await;
+        info!("{:?}", records);
+    }
+
+    #[test]
+    fn test_progress() {
+        let line = "info depth 12 seldepth 18 multipv 2 score cp -35 nodes 81234 nps 406170 hashfull 12 time 200 pv \
+                    b2e2 h9g7 b0c2";
+        let progress = Progress::parse(line).unwrap();
+        assert_eq!((progress.multipv, progress.depth, progress.seldepth), (2, 12, 18));
+        assert_eq!((progress.score, progress.nodes, progress.nps, progress.time), (-35, 81234, 406170, 200));
+        assert_eq!(progress.pvs, ["b2e2", "h9g7", "b0c2"]);
+
+        let progress = Progress::parse("info depth 3 score mate 2 pv h2h9 i9h9 c0e2").unwrap();
+        assert_eq!((progress.multipv, progress.score), (1, 29998));
+        assert_eq!(Progress::parse("info depth 3 score mate -1 pv e9e8").unwrap().score, -29999);
+
+        // 没有pv或者只是上下界的输出不算完成的一层
+        assert!(Progress::parse("info string NNUE evaluation using pikafish.nnue enabled").is_none());
+        assert!(Progress::parse("info depth 2 currmove b0c2 currmovenumber 3").is_none());
+        assert!(Progress::parse("info depth 9 score cp 20 lowerbound nodes 100 pv h2e2").is_none());
+        assert!(Progress::parse("bestmove h2e2 ponder h9g7").is_none());
+    }
+
+    #[test]
+    fn test_update_latest() {
+        let mut latest = Vec::new();
+        for line in [
+            "info depth 1 multipv 1 score cp 20 pv h2e2",
+            "info depth 1 multipv 2 score cp 10 pv b2e2",
+            "info depth 2 multipv 1 score cp 30 pv h2e2 h9g7",
+        ] {
+            update_latest(&mut latest, Progress::parse(line).unwrap());
+        }
+        assert_eq!(latest.len(), 2);
+        assert_eq!((latest[0].depth, latest[0].score), (2, 30));
+        assert_eq!(latest[1].pvs, ["b2e2"]);
+
+        let candidate = Candidate::from(latest.remove(0));
+        assert_eq!((candidate.depth, candidate.score, candidate.pvs.len()), (2, 30, 2));
+    }
+
+    // 模拟引擎的脚本: go之后按MultiPV输出一层思考, 收到stop才给出bestmove, 深度为1时直接给出
+    #[cfg(target_os = "linux")]
+    const FAKE_ENGINE: &str = r#"#!/bin/sh
+n=0
+mpv=1
+info() {
+    i=1
+    while [ $i -le $mpv ]; do
+        echo "info depth $n multipv $i score cp $((n - i)) nodes 100 nps 1000 time 1 pv h2e2 h9g7"
+        i=$((i + 1))
+    done
+}
+while read -r cmd; do
+    case "$cmd" in
+        "setoption name MultiPV value "*) mpv=${cmd##* } ;;
+        "go ponder"*) n=$((n + 1)) ;;
+        ponderhit) info; echo "bestmove h2e2" ;;
+        "go perft 2") echo "h2e2: 44"; echo "b0c2: 44"; echo; echo "Nodes searched: 88" ;;
+        "go perft 3") sleep 1; echo "h2e2: 1920"; echo; echo "Nodes searched: 1920" ;;
+        "go depth 1 "*) n=$((n + 1)); info; echo "bestmove h2e2" ;;
+        go*) n=$((n + 1)); info ;;
+        stop) echo "bestmove h2e2" ;;
+        quit) exit 0 ;;
+    esac
+done
+"#;
+
+    // 每个测试使用单独的目录, 返回的目录由测试结束时删除
+    #[cfg(target_os = "linux")]
+    fn fake_engine(name: &str) -> (Engine, path::PathBuf) {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("xqlink-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("pikafish-linux");
+        fs::write(&script, FAKE_ENGINE).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        (Engine::new(&dir), dir)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_cancel() {
+        let (mut eng, dir) = fake_engine("cancel");
+        let cfg = EngineConfig { chessdb_enabled: false, ..Default::default() };
+        let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        for depth in 1..=2 {
+            let cancel = CancellationToken::new();
+            let mut depths = Vec::new();
+            let on_progress = |progress: &Progress| {
+                depths.push(progress.depth);
+                cancel.cancel();
+            };
+            assert!(eng.search_with_progress(fen, &cfg, &cancel, on_progress).await.is_none());
+            // 取消后等到了bestmove, 下一次搜索不会读到这次的输出
+            assert_eq!(depths, [depth]);
+        }
+
+        // 无限分析取消后返回停止前的思考
+        let cfg = EngineConfig { infinite: true, ..cfg };
+        let cancel = CancellationToken::new();
+        let result = eng.search_with_progress(fen, &cfg, &cancel, |_| cancel.cancel()).await.unwrap();
+        assert_eq!((result.depth, result.source.as_str()), (3, SOURCE_ENGINE));
+        assert_eq!(result.pvs, ["h2e2", "h9g7"]);
+
+        drop(eng);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // 默认配置启用了云库, 需要多条候选时仍然调用引擎
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_multipv() {
+        let (mut eng, dir) = fake_engine("multipv");
+        let cfg = EngineConfig { depth: 1, multipv: 3, ..Default::default() };
+        assert!(cfg.chessdb_enabled);
+        let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        let result = eng.search(fen, &cfg).await.unwrap();
+        assert_eq!(result.source, SOURCE_ENGINE);
+        let scores: Vec<isize> = result.candidates.iter().map(|candidate| candidate.score).collect();
+        assert_eq!(scores, [0, -1, -2]);
+
+        drop(eng);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_ponder() {
+        let (mut eng, dir) = fake_engine("ponder");
+        let cfg = EngineConfig { chessdb_enabled: false, ..Default::default() };
+        let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        let moves = ["h2e2".to_string(), "h9g7".to_string()];
+
+        // 未命中时stop, 结果作废
+        let (hit, cancel) = (CancellationToken::new(), CancellationToken::new());
+        cancel.cancel();
+        assert!(eng.ponder(fen, &moves, &cfg, &hit, &cancel, |_| {}).await.is_none());
+
+        // 命中后发送ponderhit, 得到正常的搜索结果
+        let (hit, cancel) = (CancellationToken::new(), CancellationToken::new());
+        hit.cancel();
+        let mut depths = Vec::new();
+        let result =
+            eng.ponder(fen, &moves, &cfg, &hit, &cancel, |progress| depths.push(progress.depth)).await.unwrap();
+        assert_eq!((result.depth, result.source.as_str()), (2, SOURCE_ENGINE));
+        assert_eq!(depths, [2]);
+
+        drop(eng);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_perft() {
+        let (mut eng, dir) = fake_engine("perft");
+        let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w";
+        let result = eng.perft(fen, 2, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(result, [("b0c2".to_string(), 44), ("h2e2".to_string(), 44)]);
+        // 引擎一直没有给出结果时超时返回错误, 重启后不会读到这次的输出
+        assert!(eng.perft(fen, 3, Duration::from_millis(200)).await.is_err());
+        let result = eng.perft(fen, 2, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(result, [("b0c2".to_string(), 44), ("h2e2".to_string(), 44)]);
+
+        drop(eng);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // 使用引擎的 go perft 校验本地走法生成, 引擎目录通过 XQLINK_LIBS 指定
+    #[tokio::test]
+    #[ignore = "需要引擎及nnue文件"]
+    async fn test_perft_cross_check() {
+        let libs =
+            std::env::var("XQLINK_LIBS").map(path::PathBuf::from).unwrap_or(path::PathBuf::from("../libs/pikafish"));
+        let mut eng = Engine::new(&libs);
+        for fen in [
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w",
+            "r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w",
+            "1cbak4/9/n2a5/2p1p3p/5cp2/2n2N3/6PCP/3AB4/2C6/3A1K1N1 w",
+            "5a3/3k5/3aR4/9/5r3/5n3/9/3A1A1N1/5K3/2BC2B2 w",
+            "CRN1k1b2/3ca4/4ba3/9/2nr5/9/9/4B4/4A4/4KA3 w",
+        ] {
+            let ours = chess::perft::divide(&fen.parse().unwrap(), 3);
+            let theirs = eng.perft(fen, 3, Duration::from_secs(60)).await.unwrap();
+            let diff = chess::perft::divide_diff(&ours, &theirs);
+            assert!(diff.is_empty(), "{} => {:?}", fen, diff);
+        }
+    }
+}
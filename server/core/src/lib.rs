@@ -0,0 +1,8 @@
+// 棋盘识别与引擎分析的核心库, 不依赖Tauri, 也没有全局状态
+pub mod chess;
+pub mod common;
+pub mod engine;
+pub mod pgn;
+pub mod recognize;
+pub mod record;
+pub mod yolo;
@@ -0,0 +1,185 @@
+use serde::Deserialize;
+
+use crate::chess;
+use crate::record::Evaluation;
+use crate::record::GameRecord;
+
+// 每行棋谱的最大长度
+const LINE_WIDTH: usize = 80;
+
+// 导出棋谱的着法格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+pub enum MoveFormat {
+    #[default]
+    Iccs, // 坐标, 如 H2-E2
+    Chinese, // 中文, 如 炮二平五
+    Wxf,     // WXF, 如 C2=5
+}
+
+impl MoveFormat {
+    fn name(self) -> &'static str {
+        match self {
+            MoveFormat::Iccs => "ICCS",
+            MoveFormat::Chinese => "Chinese",
+            MoveFormat::Wxf => "WXF",
+        }
+    }
+
+    fn format(self, board: &chess::Board, mv: chess::Move) -> String {
+        match self {
+            MoveFormat::Iccs => {
+                let iccs = mv.to_string().to_uppercase();
+                format!("{}-{}", &iccs[..2], &iccs[2..])
+            }
+            MoveFormat::Chinese => chess::board_move_chinese(board, mv, chess::Locale::Simplified),
+            MoveFormat::Wxf => chess::wxf::board_move_wxf(board, mv),
+        }
+    }
+}
+
+// 时间戳(毫秒)转换为UTC日期, 如 2025.01.31
+fn date(millis: u64) -> String {
+    let days = (millis / 86_400_000) as i64 + 719_468;
+    let era = days / 146_097;
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+// 终局时按胜方给出结果, 否则为未结束
+fn result(board: &chess::Board) -> &'static str {
+    match chess::movegen::game_over(board) {
+        Some(over) if over.winner == chess::Color::Red => "1-0",
+        Some(_) => "0-1",
+        None => "*",
+    }
+}
+
+// 棋谱转换为PGN文本
+pub fn to_pgn(record: &GameRecord, format: MoveFormat) -> String {
+    let mut board = record.start();
+    let mut tokens = Vec::new();
+    for (i, recorded) in record.moves.iter().enumerate() {
+        let Ok(mv) = recorded.iccs.parse::<chess::Move>() else { break };
+        board = board.with_turn(board.get(mv.from).map_or(board.turn, |piece| piece.color));
+        match board.turn {
+            chess::Color::Red => tokens.push(format!("{}.", board.fullmove)),
+            chess::Color::Black if i == 0 => tokens.push(format!("{}...", board.fullmove)),
+            chess::Color::Black => {}
+        }
+        tokens.push(format.format(&board, mv));
+        if let Some(eval) = &recorded.eval {
+            // 引擎的分数以行棋方为准, 统一转换为红方的角度
+            let score = if board.turn == chess::Color::Black { -eval.score } else { eval.score };
+            tokens.push(format!("{{{}}}", Evaluation { score, ..eval.clone() }));
+        }
+        board = board.make_move(mv);
+    }
+    let result = result(&board);
+    tokens.push(result.to_string());
+
+    let mut pgn = String::new();
+    for (tag, value) in [
+        ("Game", "Chinese Chess"),
+        ("Event", "?"),
+        ("Site", "?"),
+        ("Date", &date(record.start_time)),
+        ("Round", "?"),
+        ("Red", "?"),
+        ("Black", "?"),
+        ("Result", result),
+        ("FEN", &record.start_fen),
+        ("Format", format.name()),
+    ] {
+        pgn.push_str(&format!("[{} \"{}\"]\n", tag, value));
+    }
+    pgn.push('\n');
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.chars().count() + token.chars().count() + 1 > LINE_WIDTH {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    pgn.push_str(&line);
+    pgn.push('\n');
+    pgn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fen: &str, moves: &[&str]) -> GameRecord {
+        let mut record = GameRecord::new(fen.parse().unwrap());
+        for mv in moves {
+            record.push(mv.parse().unwrap());
+        }
+        record
+    }
+
+    #[test]
+    fn test_to_pgn() {
+        let start = chess::Board::startpos().to_string();
+        let mut record = record(&start, &["h2e2"]);
+        let board = chess::Board::startpos().make_move("h2e2".parse().unwrap());
+        // 黑方行棋时的分数, 导出时转换为红方的角度
+        record.evaluate(&board, Evaluation { score: -25, depth: 18, best: None });
+        record.push("h9g7".parse().unwrap());
+        let board = record.board();
+        record.evaluate(&board, Evaluation { score: 30, depth: 20, best: None });
+        record.push("h0g2".parse().unwrap());
+        record.start_time = 1_735_603_200_000;
+
+        let pgn = to_pgn(&record, MoveFormat::Iccs);
+        assert!(pgn.starts_with("[Game \"Chinese Chess\"]\n"));
+        assert!(pgn.contains("[Date \"2024.12.31\"]\n"));
+        assert!(pgn.contains(&format!("[FEN \"{}\"]\n", start)));
+        assert!(pgn.contains("[Format \"ICCS\"]\n"));
+        assert!(pgn.ends_with("\n\n1. H2-E2 H9-G7 {+0.25/18} 2. H0-G2 {+0.30/20} *\n"));
+
+        let pgn = to_pgn(&record, MoveFormat::Chinese);
+        assert!(pgn.ends_with("\n1. 炮二平五 马8进7 {+0.25/18} 2. 马二进三 {+0.30/20} *\n"));
+        let pgn = to_pgn(&record, MoveFormat::Wxf);
+        assert!(pgn.contains("[Format \"WXF\"]\n"));
+        assert!(pgn.ends_with("\n1. C2=5 H8+7 {+0.25/18} 2. H2+3 {+0.30/20} *\n"));
+    }
+
+    #[test]
+    fn test_black_first() {
+        // 黑方先走, 以及终局结果
+        let record = record("1r3k3/9/9/9/9/9/9/9/r8/3K5 w", &["b9b0"]);
+        let pgn = to_pgn(&record, MoveFormat::Iccs);
+        assert!(pgn.contains("[FEN \"1r3k3/9/9/9/9/9/9/9/r8/3K5 b - - 0 1\"]\n"));
+        assert!(pgn.contains("[Result \"0-1\"]\n"));
+        assert!(pgn.ends_with("\n1... B9-B0 0-1\n"));
+    }
+
+    #[test]
+    fn test_line_width() {
+        let moves = ["h0g2", "h9g7", "g2h0", "g7h9"];
+        let record = record(&chess::Board::startpos().to_string(), &moves.repeat(10));
+        let pgn = to_pgn(&record, MoveFormat::Iccs);
+        let text = pgn.split("\n\n").nth(1).unwrap();
+        assert!(text.lines().count() > 1);
+        assert!(text.lines().all(|line| line.len() <= LINE_WIDTH));
+        assert!(text.starts_with("1. H0-G2 H9-G7 2. G2-H0 G7-H9 3."));
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(date(0), "1970.01.01");
+        assert_eq!(date(951_782_400_000), "2000.02.29");
+        assert_eq!(date(1_735_603_200_000), "2024.12.31");
+    }
+}
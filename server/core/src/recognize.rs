@@ -0,0 +1,177 @@
+use image::imageops;
+use image::ImageBuffer;
+use image::Rgba;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::chess;
+use crate::common;
+use crate::yolo::predict;
+
+// 待识别图片的来源
+#[derive(Debug, Deserialize)]
+pub enum ImageSource {
+    Path(String),   // PNG/JPEG文件路径
+    Bytes(Vec<u8>), // 剪贴板中的图片数据
+}
+
+impl ImageSource {
+    pub fn load(&self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, String> {
+        let image = match self {
+            ImageSource::Path(path) => image::open(path),
+            ImageSource::Bytes(data) => image::load_from_memory(data),
+        };
+        image.map(|image| image.to_rgba8()).map_err(|e| format!("读取图片失败: {}", e))
+    }
+}
+
+// 图片识别结果
+#[derive(Debug, Serialize)]
+pub struct Recognition {
+    pub camp: chess::Camp,
+    pub board: Vec<chess::Position>,
+    pub fen: String,
+}
+
+// 识别图片中的棋盘: 先定位棋盘并裁剪, 再识别裁剪后图片中的棋子
+pub fn recognize(image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<(chess::Camp, chess::Board), String> {
+    let detections = predict(image.clone()).map_err(|e| e.to_string())?;
+    let (x, y, w, h) = common::detections_bound(image.width(), image.height(), &detections)?;
+    let cropped = imageops::crop_imm(&image, x, y, w, h).to_image();
+    let detections = predict(cropped).map_err(|e| e.to_string())?;
+    let (camp, mut board) = common::detections_to_board(&detections)?;
+    board.fix(&camp);
+    Ok((camp, board))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // 棋子类别, 空位记为 '.'
+    fn class(board: &chess::Board, sq: chess::Square) -> char { board.get(sq).map_or('.', chess::Piece::to_char) }
+
+    // 识别准确率统计
+    #[derive(Default)]
+    struct Report {
+        images: usize,
+        failures: usize,
+        confusion: BTreeMap<(char, char), usize>, // (期望, 识别) -> 格数
+        lines: Vec<String>,
+    }
+
+    impl Report {
+        fn add(&mut self, name: &str, expected: &chess::Board, actual: Result<chess::Board, String>) {
+            self.images += 1;
+            let actual = match actual {
+                Ok(board) => board,
+                Err(e) => {
+                    self.failures += 1;
+                    self.lines.push(format!("{}: 识别失败 {}", name, e));
+                    return;
+                }
+            };
+            let mut correct = 0;
+            for sq in chess::Square::all() {
+                let (want, got) = (class(expected, sq), class(&actual, sq));
+                *self.confusion.entry((want, got)).or_default() += 1;
+                correct += usize::from(want == got);
+            }
+            self.lines.push(format!("{}: {}/90 {:.1}%", name, correct, correct as f64 / 0.9));
+            if correct < 90 {
+                self.failures += 1;
+                self.lines.push(format!("  期望 {}\n  识别 {}", expected, actual));
+            }
+        }
+
+        fn summary(&self) -> String {
+            let mut text = self.lines.join("\n");
+            text.push_str("\n各类别准确率(期望: 正确/总数 误识别为):\n");
+            for want in "KABNRCPkabnrcp.".chars() {
+                let row: Vec<(char, usize)> =
+                    self.confusion.iter().filter(|((w, _), _)| *w == want).map(|((_, got), n)| (*got, *n)).collect();
+                let total: usize = row.iter().map(|(_, n)| n).sum();
+                if total == 0 {
+                    continue;
+                }
+                let correct = self.confusion.get(&(want, want)).copied().unwrap_or(0);
+                let errors: Vec<String> =
+                    row.iter().filter(|(got, _)| *got != want).map(|(got, n)| format!("{}x{}", got, n)).collect();
+                text.push_str(&format!("{}: {}/{} {}\n", want, correct, total, errors.join(" ")));
+            }
+            text.push_str(&format!("共{}张图片, 失败{}张", self.images, self.failures));
+            text
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let expected = chess::Board::startpos();
+        let mut actual = expected;
+        actual.set("b2".parse().unwrap(), chess::Piece::from_char('N'));
+        let mut report = Report::default();
+        report.add("same", &expected, Ok(expected));
+        report.add("wrong", &expected, Ok(actual));
+        report.add("error", &expected, Err("未识别到棋盘".to_string()));
+        assert_eq!((report.images, report.failures), (3, 2));
+        assert_eq!(report.confusion[&('C', 'N')], 1);
+        assert_eq!(report.confusion[&('C', 'C')], 3);
+        let summary = report.summary();
+        assert!(summary.contains("wrong: 89/90 98.9%"));
+        assert!(summary.contains("\nC: 3/4 Nx1\n"));
+        assert!(summary.ends_with("共3张图片, 失败2张"));
+    }
+
+    // 图片识别回归测试, 目录中每个 .fen 文件对应一张同名的 png/jpg 截图
+    // 仓库中没有附带截图, 需要用环境变量 XQLINK_GOLDEN_DIR 指定目录后以 --ignored 运行, 目录中没有截图时失败
+    // Linux下需用 ORT_DYLIB_PATH 指定onnxruntime动态库, 没有CUDA时使用CPU推理
+    #[test]
+    #[ignore = "需要设置 XQLINK_GOLDEN_DIR 为存放截图的目录, Linux下还需 ORT_DYLIB_PATH"]
+    fn test_golden() {
+        let dir = env::var("XQLINK_GOLDEN_DIR")
+            .map_or_else(|_| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden"), PathBuf::from);
+        let mut samples: Vec<PathBuf> = fs::read_dir(&dir)
+            .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        samples.retain(|path| path.extension().is_some_and(|ext| ext == "fen"));
+        samples.sort();
+        assert!(!samples.is_empty(), "{} 中没有测试图片, 请设置 XQLINK_GOLDEN_DIR", dir.display());
+
+        let mut report = Report::default();
+        for path in samples {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let expected: chess::Board = fs::read_to_string(&path).unwrap().trim().parse().unwrap();
+            let actual = ["png", "jpg", "jpeg"]
+                .iter()
+                .map(|ext| path.with_extension(ext))
+                .find(|image| image.exists())
+                .ok_or_else(|| "缺少图片".to_string())
+                .and_then(|image| ImageSource::Path(image.to_string_lossy().into_owned()).load())
+                .and_then(recognize)
+                .map(|(_, board)| board);
+            report.add(&name, &expected, actual);
+        }
+        let summary = report.summary();
+        println!("{}", summary);
+        assert_eq!(report.failures, 0, "{}", summary);
+    }
+
+    #[test]
+    fn test_load() {
+        let mut data = Vec::new();
+        let image: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::from_pixel(3, 2, Rgba([200, 100, 50, 255]));
+        image.write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png).unwrap();
+        let loaded = ImageSource::Bytes(data).load().unwrap();
+        assert_eq!(loaded, image);
+
+        assert!(ImageSource::Bytes(vec![1, 2, 3]).load().is_err());
+        assert!(ImageSource::Path("not_exists.png".to_string()).load().is_err());
+    }
+}
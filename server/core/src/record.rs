@@ -0,0 +1,145 @@
+use std::fmt;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use crate::chess;
+
+// 引擎对局面的评估
+#[derive(Debug, Clone, Serialize)]
+pub struct Evaluation {
+    pub score: isize,         // 得分
+    pub depth: usize,         // 深度
+    pub best: Option<String>, // 最佳着法(iccs)
+}
+
+// 写入棋谱注释, 如 +0.30/20
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+.2}/{}", self.score as f64 / 100.0, self.depth)
+    }
+}
+
+// 棋谱中的一步棋
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordMove {
+    pub iccs: String,             // 着法(iccs)
+    pub eval: Option<Evaluation>, // 走棋前引擎对该局面的评估
+    pub time: u64,                // 时间戳(毫秒)
+}
+
+// 一局棋的完整记录
+#[derive(Debug, Clone, Serialize)]
+pub struct GameRecord {
+    pub start_fen: String,      // 起始局面
+    pub start_time: u64,        // 开始时间戳(毫秒)
+    pub moves: Vec<RecordMove>, // 着法列表
+    #[serde(skip)]
+    start: chess::Board,
+    #[serde(skip)]
+    board: chess::Board,
+    #[serde(skip)]
+    eval: Option<Evaluation>,
+}
+
+impl Default for GameRecord {
+    fn default() -> Self { Self::new(chess::Board::default()) }
+}
+
+fn now() -> u64 { SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64) }
+
+impl GameRecord {
+    pub fn new(start: chess::Board) -> Self {
+        Self { start_fen: start.to_string(), start_time: now(), moves: Vec::new(), start, board: start, eval: None }
+    }
+
+    // 起始局面, 行棋方以第一步为准
+    pub fn start(&self) -> chess::Board { self.start }
+
+    // 当前局面
+    pub fn board(&self) -> chess::Board { self.board }
+
+    // 记录引擎对当前局面的评估, 下一步棋走出时写入棋谱
+    pub fn evaluate(&mut self, board: &chess::Board, eval: Evaluation) {
+        if board.same_placement(&self.board) {
+            self.eval = Some(eval);
+        }
+    }
+
+    // 记录一步棋, 不检查合法性
+    pub fn push(&mut self, mv: chess::Move) {
+        let Some(piece) = self.board.get(mv.from) else { return };
+        if self.moves.is_empty() {
+            // 识别出的棋盘行棋方不可靠, 以第一步为准
+            self.start.turn = piece.color;
+            self.start_fen = self.start.to_string();
+        }
+        self.board = self.board.with_turn(piece.color).make_move(mv);
+        self.moves.push(RecordMove { iccs: mv.to_string(), eval: self.eval.take(), time: now() });
+    }
+
+    // 从棋谱载入主线着法, 不合法的着法及之后的着法被忽略
+    pub fn from_game(game: &chess::game::Game) -> Self {
+        let mut record = Self::new(game.start);
+        for mv in game.main_line() {
+            if !chess::movegen::is_legal_move(&record.board, mv) {
+                break;
+            }
+            record.push(mv);
+        }
+        record
+    }
+
+    // 转换为棋谱树, 引擎评估写入注释
+    pub fn to_game(&self) -> chess::game::Game {
+        let mut game = chess::game::Game::new(self.start);
+        let mut node = &mut game.root;
+        for recorded in &self.moves {
+            let Ok(mv) = recorded.iccs.parse() else { break };
+            let mut child = chess::game::Node::new(mv);
+            child.comment = recorded.eval.as_ref().map(|eval| eval.to_string()).unwrap_or_default();
+            node.children.push(child);
+            node = &mut node.children[0];
+        }
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let board = chess::Board::startpos().with_turn(chess::Color::Black);
+        let mut record = GameRecord::new(board);
+        let eval = Evaluation { score: 30, depth: 20, best: Some("h2e2".to_string()) };
+        // 与当前局面不符的评估被忽略
+        record.evaluate(&board.make_move("h2e2".parse().unwrap()), eval.clone());
+        record.push("b0c2".parse().unwrap());
+        assert!(record.moves[0].eval.is_none());
+
+        let current = record.board;
+        record.evaluate(&current, eval);
+        record.push("h9g7".parse().unwrap());
+        record.push("h2e2".parse().unwrap());
+        assert_eq!(record.start_fen, chess::Board::startpos().to_string());
+        let moves: Vec<&str> = record.moves.iter().map(|mv| mv.iccs.as_str()).collect();
+        assert_eq!(moves, ["b0c2", "h9g7", "h2e2"]);
+        assert_eq!(record.moves[1].eval.as_ref().map(|eval| eval.score), Some(30));
+        assert!(record.moves[2].eval.is_none());
+        assert!(record.moves.iter().all(|mv| mv.time >= record.start_time));
+
+        let game = record.to_game();
+        assert_eq!(game.start, record.start());
+        let moves: Vec<String> = game.main_line().iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(moves, ["b0c2", "h9g7", "h2e2"]);
+        assert_eq!(game.root.children[0].children[0].comment, "+0.30/20");
+
+        let loaded = GameRecord::from_game(&game);
+        assert_eq!(loaded.start_fen, record.start_fen);
+        assert_eq!(loaded.board().to_string(), record.board().to_string());
+        assert_eq!(loaded.moves.len(), 3);
+    }
+}
@@ -1,14 +1,14 @@
 use std::iter::Iterator;
 use std::sync::OnceLock;
 
+use image::imageops::FilterType;
+use image::DynamicImage;
+use image::GenericImageView;
+use image::ImageBuffer;
+use image::Rgba;
 use ndarray::s;
 use ndarray::Array;
 use ort::inputs;
-use xcap::image::imageops::FilterType;
-use xcap::image::DynamicImage;
-use xcap::image::GenericImageView;
-use xcap::image::ImageBuffer;
-use xcap::image::Rgba;
 
 static SESSION: OnceLock<ort::session::Session> = OnceLock::new();
 
@@ -20,10 +20,10 @@ const LABELS: [char; 15] = ['n', 'b', 'a', 'k', 'r', 'c', 'p', 'R', 'N', 'A', 'K
 const LIMIT: [usize; 15] = [2, 2, 2, 1, 2, 2, 5, 2, 2, 2, 1, 2, 2, 5, 1];
 
 #[cfg(not(feature = "rotate"))]
-const MODEL_BYTES: &[u8] = include_bytes!("../../libs/large.onnx");
+const MODEL_BYTES: &[u8] = include_bytes!("../../../libs/large.onnx");
 
 #[cfg(feature = "rotate")]
-const MODEL_BYTES: &[u8] = include_bytes!("../../libs/rotate.onnx");
+const MODEL_BYTES: &[u8] = include_bytes!("../../../libs/rotate.onnx");
 
 pub fn session() -> &'static ort::session::Session {
     SESSION.get_or_init(|| {
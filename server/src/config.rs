@@ -9,6 +9,7 @@ use serde::Deserialize;
 use serde::Serialize;
 use tracing::debug;
 
+use crate::chess;
 use crate::engine::EngineConfig;
 use crate::SHARED_STATE;
 
@@ -22,6 +23,9 @@ pub struct Config {
     pub confirm_interval: u64,
 
     pub engine: EngineConfig,
+    // 走法记谱语言
+    #[serde(default)]
+    pub notation: chess::Locale,
 }
 
 impl Default for Config {
@@ -32,6 +36,7 @@ impl Default for Config {
             timer_interval: 100,
             confirm_interval: 200,
             engine: Default::default(),
+            notation: Default::default(),
         }
     }
 }
@@ -125,6 +130,24 @@ pub async fn set_engine_hash(size: usize) {
     debug!("set_engine_hash: {}", size);
 }
 
+#[tauri::command]
+pub async fn set_engine_multipv(num: usize) {
+    let state = SHARED_STATE.get().unwrap();
+    let mut config = state.config.write().unwrap();
+    config.engine.multipv = num;
+    config.save();
+    debug!("set_engine_multipv: {}", num);
+}
+
+#[tauri::command]
+pub async fn set_engine_infinite(enabled: bool) {
+    let state = SHARED_STATE.get().unwrap();
+    let mut config = state.config.write().unwrap();
+    config.engine.infinite = enabled;
+    config.save();
+    debug!("set_engine_infinite: {}", enabled);
+}
+
 #[tauri::command]
 pub async fn set_chessdb(enabled: bool, timeout: Option<u64>) {
     let state = SHARED_STATE.get().unwrap();
@@ -135,3 +158,15 @@ pub async fn set_chessdb(enabled: bool, timeout: Option<u64>) {
     config.save();
     debug!("set_chessdb: {} -> {}", enabled, timeout);
 }
+
+#[tauri::command]
+pub async fn get_notation() -> chess::Locale { SHARED_STATE.get().unwrap().config.read().unwrap().notation }
+
+#[tauri::command]
+pub async fn set_notation(locale: chess::Locale) {
+    let state = SHARED_STATE.get().unwrap();
+    let mut config = state.config.write().unwrap();
+    config.notation = locale;
+    config.save();
+    debug!("set_notation: {:?}", locale);
+}
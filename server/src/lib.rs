@@ -4,23 +4,31 @@ use std::sync::OnceLock;
 use std::sync::RwLock;
 use std::thread;
 
-use engine::Engine;
 use tauri::Manager as _;
+use xqlink_core::chess;
+use xqlink_core::common;
+use xqlink_core::engine;
+use xqlink_core::engine::CancellationToken;
+use xqlink_core::engine::Engine;
+use xqlink_core::recognize;
+use xqlink_core::record::GameRecord;
+use xqlink_core::yolo;
 
-mod chess;
-mod common;
 mod config;
-mod engine;
 mod listen;
 mod logger;
+mod pgn;
+mod record;
 mod worker;
-mod yolo;
 
 // 全局共享状态，用Arc和Mutex包装以实现线程安全共享
 struct SharedState {
     config: Arc<RwLock<config::Config>>,
     engine: Arc<Mutex<Engine>>,
     listen_thread: Mutex<Option<thread::JoinHandle<()>>>,
+    record: RwLock<GameRecord>,
+    search_stop: Mutex<CancellationToken>, // 当前引擎搜索的停止信号
+    analysis: Mutex<CancellationToken>,    // analyse_fen的分析任务, 载入新局面时取消
 }
 
 static SHARED_STATE: OnceLock<SharedState> = OnceLock::new();
@@ -43,6 +51,9 @@ pub fn run() {
                     config: Arc::new(RwLock::new(config)),
                     engine: Arc::new(Mutex::new(engine)),
                     listen_thread: Mutex::new(None),
+                    record: RwLock::new(GameRecord::default()),
+                    search_stop: Mutex::new(CancellationToken::new()),
+                    analysis: Mutex::new(CancellationToken::new()),
                 }
             });
 
@@ -54,12 +65,26 @@ pub fn run() {
             listen::list_windows,
             worker::start_listen,
             worker::stop_listen,
+            worker::analyse_fen,
+            worker::stop_analyse,
             config::get_engine_config,
             config::set_engine_depth,
             config::set_engine_time,
             config::set_engine_threads,
             config::set_engine_hash,
+            config::set_engine_multipv,
+            config::set_engine_infinite,
             config::set_chessdb,
+            config::get_notation,
+            config::set_notation,
+            record::get_game_record,
+            record::parse_move,
+            record::export_xqf,
+            record::import_xqf,
+            record::import_dhtmlxq,
+            record::export_dhtmlxq,
+            pgn::export_pgn,
+            worker::recognize_image,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,16 @@
+use std::fs;
+
+use tracing::debug;
+use xqlink_core::pgn::to_pgn;
+use xqlink_core::pgn::MoveFormat;
+
+use crate::SHARED_STATE;
+
+// 导出当前对局为PGN文件
+#[tauri::command]
+pub async fn export_pgn(path: String, format: MoveFormat) -> Result<(), String> {
+    let record = SHARED_STATE.get().unwrap().record.read().unwrap().clone();
+    fs::write(&path, to_pgn(&record, format)).map_err(|e| format!("导出棋谱失败: {}", e))?;
+    debug!("export_pgn: {} {:?}", path, format);
+    Ok(())
+}
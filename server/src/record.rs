@@ -0,0 +1,66 @@
+use std::fs;
+
+use tracing::debug;
+use xqlink_core::record::GameRecord;
+
+use crate::chess;
+use crate::SHARED_STATE;
+
+// 把用户输入的ICCS、WXF或中文着法解析为ICCS, 未指定FEN时按当前对局的局面解析
+#[tauri::command]
+pub async fn parse_move(text: String, fen: Option<String>) -> Result<String, String> {
+    let board = match fen {
+        Some(fen) => fen.parse()?,
+        None => SHARED_STATE.get().unwrap().record.read().unwrap().board(),
+    };
+    let mv = chess::parse_move(&board, &text)?;
+    debug!("parse_move: {} -> {}", text, mv);
+    Ok(mv.to_string())
+}
+
+#[tauri::command]
+pub async fn get_game_record() -> GameRecord { SHARED_STATE.get().unwrap().record.read().unwrap().clone() }
+
+// 导出当前对局为XQF文件
+#[tauri::command]
+pub async fn export_xqf(path: String) -> Result<(), String> {
+    let game = SHARED_STATE.get().unwrap().record.read().unwrap().to_game();
+    let data = chess::xqf::write_xqf(&game)?;
+    fs::write(&path, data).map_err(|e| format!("导出棋谱失败: {}", e))?;
+    debug!("export_xqf: {}", path);
+    Ok(())
+}
+
+// 棋谱主线载入为当前对局, 返回主线走完后的FEN供分析, 监听中时不可用
+fn load_game(game: &chess::game::Game) -> Result<String, String> {
+    let state = SHARED_STATE.get().unwrap();
+    if state.listen_thread.lock().unwrap().is_some() {
+        return Err("正在监听中, 请先停止监听".to_string());
+    }
+    let record = GameRecord::from_game(game);
+    let fen = record.board().to_string();
+    debug!("load_game: {} moves -> {}", record.moves.len(), fen);
+    *state.record.write().unwrap() = record;
+    Ok(fen)
+}
+
+// 导入XQF棋谱
+#[tauri::command]
+pub async fn import_xqf(path: String) -> Result<String, String> {
+    let data = fs::read(&path).map_err(|e| format!("读取棋谱失败: {}", e))?;
+    debug!("import_xqf: {}", path);
+    load_game(&chess::xqf::read_xqf(&data)?)
+}
+
+// 导入论坛分享的DhtmlXQ棋谱
+#[tauri::command]
+pub async fn import_dhtmlxq(text: String) -> Result<String, String> {
+    load_game(&chess::dhtmlxq::parse_dhtmlxq(&text)?)
+}
+
+// 导出当前对局为DhtmlXQ文本, 用于分享到论坛
+#[tauri::command]
+pub async fn export_dhtmlxq() -> Result<String, String> {
+    let game = SHARED_STATE.get().unwrap().record.read().unwrap().to_game();
+    chess::dhtmlxq::to_dhtmlxq(&game)
+}
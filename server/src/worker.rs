@@ -1,3 +1,4 @@
+use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 
@@ -10,21 +11,45 @@ use tracing::info;
 use tracing::trace;
 use xcap::image::ImageBuffer;
 use xcap::image::Rgba;
+use xqlink_core::record::Evaluation;
+use xqlink_core::record::GameRecord;
 
 use crate::chess;
 use crate::common;
+use crate::engine::CancellationToken;
+use crate::engine::Progress;
 use crate::engine::QueryResult;
 use crate::listen::ListenWindow;
 use crate::listen::Window;
+use crate::recognize;
 use crate::yolo::predict;
 use crate::yolo::IMAGE_HEIGHT;
 use crate::yolo::IMAGE_WIDTH;
 use crate::SHARED_STATE;
 
+// 发送给前端的思考步数
+const PV_MOVES: usize = 4;
+
 // 棋盘分析结果
 struct BoardAnalysisResult {
     expect_move: chess::Changed,
-    expect_board: [[char; 9]; 10],
+    expect_board: chess::Board,
+    ponder_move: Option<chess::Move>, // 预期着法之后对方的预期应着
+}
+
+// 后台思考(ponder)的局面: 对局的起始局面和之后的全部着法, 最后一步是对方的预期应着
+struct Ponder {
+    start: String,
+    moves: Vec<String>,
+    hit: CancellationToken, // 对方走出预期应着后触发
+}
+
+// 后台进行中的引擎分析
+struct PendingAnalysis {
+    board: chess::Board,
+    cancel: CancellationToken,
+    ponderhit: Option<CancellationToken>, // 后台思考(ponder)时, 对方走出预期应着后触发
+    result: mpsc::Receiver<Option<QueryResult>>,
 }
 
 // 定义不同的棋盘状态
@@ -41,10 +66,13 @@ enum ChessboardState {
 struct AnalysisContext {
     app: AppHandle,
     window: ListenWindow,
-    last_board: [[char; 9]; 10],
-    expect_move: chess::Changed,
-    expect_board: [[char; 9]; 10],
+    last_board: chess::Board,
+    expect_move: Option<chess::Changed>,
+    expect_board: chess::Board,
+    ponder_move: Option<chess::Move>,
+    history: chess::repetition::History,
     invalid_change_count: usize,
+    pending: Option<PendingAnalysis>,
 }
 
 unsafe impl Send for AnalysisContext {}
@@ -56,10 +84,13 @@ impl AnalysisContext {
             app,
             // state_for_thread: state,
             window,
-            last_board: [[' '; 9]; 10],
-            expect_move: chess::Changed::default(),
-            expect_board: [[' '; 9]; 10],
+            last_board: chess::Board::default(),
+            expect_move: None,
+            expect_board: chess::Board::default(),
+            ponder_move: None,
+            history: chess::repetition::History::new(chess::Board::default()),
             invalid_change_count: 0,
+            pending: None,
         }
     }
 
@@ -70,52 +101,162 @@ impl AnalysisContext {
     }
 
     // 获取棋盘图像并分析
-    fn capture_and_analyze_board(&self) -> Option<(chess::Camp, [[char; 9]; 10])> {
+    fn capture_and_analyze_board(&self) -> Option<(chess::Camp, chess::Board)> {
         let image = self.window.capture();
         get_board(image)
     }
 
     // 确认棋盘状态是否稳定
-    fn confirm_board(&self, board: [[char; 9]; 10]) -> bool {
+    fn confirm_board(&self, board: &chess::Board) -> bool {
         thread::sleep(Duration::from_millis(100));
         let conf_image = self.window.capture();
         if let Some((_, conf_board)) = get_board(conf_image) {
-            return conf_board == board;
+            return conf_board.same_placement(board);
         }
         false
     }
 
-    // 分析棋盘并返回结果
-    fn analyze_board(&mut self, camp: &chess::Camp, board: [[char; 9]; 10]) -> Option<BoardAnalysisResult> {
-        let fen = chess::board_fen(camp, board);
-        let config = SHARED_STATE.get().unwrap().config.read().unwrap();
-        let state = SHARED_STATE.get().unwrap();
-        let mut engine = state.engine.lock().unwrap();
-        let result = block_on(engine.search(&fen, &config.engine));
-        result.as_ref()?;
+    // 检查行棋方是否被将死或困毙, 对局结束时通知前端
+    fn check_game_over(&self, board: &chess::Board) -> bool {
+        match chess::movegen::game_over(board) {
+            Some(result) => {
+                info!("对局结束 {:?}", result);
+                self.app.emit("gameover", result).unwrap();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 在后台线程中分析棋盘, 先取消还在进行的分析
+    fn analyze_board(&mut self, board: chess::Board) {
+        self.expect_move = None;
+        self.ponder_move = None;
+        // 对方走出了预期的应着, 后台思考已经转为正常搜索
+        if self.pending.as_ref().is_some_and(|pending| pending.board.same_placement(&board)) {
+            return;
+        }
+        self.cancel_analysis();
+        // 已经结束的对局不再送引擎分析
+        if self.check_game_over(&board) {
+            return;
+        }
+        self.spawn_search(board, None);
+    }
+
+    // 我方走出预期着法后, 在对方思考时后台思考对方的预期应着, 无限分析时不需要
+    fn start_ponder(&mut self) {
+        let Some(mv) = self.ponder_move.take() else {
+            return;
+        };
+        let current = self.history.board();
+        if SHARED_STATE.get().unwrap().config.read().unwrap().engine.infinite
+            || !chess::movegen::is_legal_move(&current, mv)
+        {
+            return;
+        }
+        let board = current.make_move(mv);
+        if chess::movegen::game_over(&board).is_some() {
+            return;
+        }
+        debug!("后台思考 {} {}", mv, board);
+        // 引擎需要完整的着法历史才能判断循环局面
+        let mut moves: Vec<String> = self.history.moves().iter().map(ToString::to_string).collect();
+        moves.push(mv.to_string());
+        let ponder = Ponder { start: self.history.start().to_string(), moves, hit: CancellationToken::new() };
+        self.spawn_search(board, Some(ponder));
+    }
+
+    fn spawn_search(&mut self, board: chess::Board, ponder: Option<Ponder>) {
+        let cancel = CancellationToken::new();
+        let (tx, rx) = mpsc::channel();
+        let ponderhit = ponder.as_ref().map(|ponder| ponder.hit.clone());
+        let (app, token) = (self.app.clone(), cancel.clone());
+        thread::spawn(move || {
+            let _ = tx.send(search(&app, &board, ponder.as_ref(), &token));
+        });
+        self.pending = Some(PendingAnalysis { board, cancel, ponderhit, result: rx });
+    }
+
+    // 对方走出预期的应着时触发ponderhit, 后台思考转为正常搜索
+    fn ponderhit(&mut self) -> bool {
+        let Some(pending) = self.pending.as_mut() else {
+            return false;
+        };
+        match pending.ponderhit.take() {
+            Some(hit) if pending.board.same_placement(&self.last_board) => {
+                debug!("ponderhit {}", pending.board);
+                hit.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // 取消进行中的分析, 引擎收到stop后尽快返回
+    fn cancel_analysis(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            debug!("取消分析 {}", pending.board);
+            pending.cancel.cancel();
+        }
+    }
 
-        let (expect_move, expect_board) = analyse(&self.app, result.unwrap(), board);
-        Some(BoardAnalysisResult { expect_move, expect_board })
+    // 取回后台分析的结果, 棋盘已经变化时丢弃
+    fn poll_analysis(&mut self) -> Option<BoardAnalysisResult> {
+        let result = match self.pending.as_ref()?.result.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        let board = self.pending.take()?.board;
+        if !board.same_placement(&self.last_board) {
+            debug!("棋盘已变化, 丢弃分析结果 {}", board);
+            return None;
+        }
+        let result = result?;
+        let ponder = result.pvs.get(1).and_then(|pv| pv.parse::<chess::Move>().ok());
+        let eval = Evaluation { score: result.score, depth: result.depth, best: result.pvs.first().cloned() };
+        SHARED_STATE.get().unwrap().record.write().unwrap().evaluate(&board, eval);
+
+        let notation = SHARED_STATE.get().unwrap().config.read().unwrap().notation;
+        let (expect_move, expect_board) = analyse(&self.app, result, board, &self.history, notation)?;
+        let ponder_move = ponder.filter(|mv| chess::movegen::is_legal_move(&expect_board, *mv));
+        Some(BoardAnalysisResult { expect_move, expect_board, ponder_move })
     }
 
     // 更新UI显示
-    fn update_ui(&self, camp: &chess::Camp, board: [[char; 9]; 10]) {
-        let board_map = chess::board_map(board);
+    fn update_ui(&self, camp: &chess::Camp, board: &chess::Board) {
+        let board_map = board.map();
         self.app.emit("mirror", camp.is_black()).unwrap();
         self.app.emit("position", &board_map).unwrap();
     }
 
-    // 处理移动事件
-    fn handle_move(&mut self, changed: &chess::Changed) { self.app.emit("move", changed).unwrap(); }
+    // 新的对局, 重置着法历史和棋谱
+    fn reset_game(&mut self, board: chess::Board) {
+        self.history = chess::repetition::History::new(board);
+        *SHARED_STATE.get().unwrap().record.write().unwrap() = GameRecord::new(board);
+    }
+
+    // 处理移动事件, 记录到着法历史和棋谱
+    fn handle_move(&mut self, changed: &chess::Changed) {
+        // 走子后原局面的分析已经没有意义, 除非对方走出了后台思考的预期应着
+        if !self.ponderhit() {
+            self.cancel_analysis();
+        }
+        self.app.emit("move", changed).unwrap();
+        self.history.push(changed.mv());
+        SHARED_STATE.get().unwrap().record.write().unwrap().push(changed.mv());
+        if let Some(repetition) = self.history.repetition() {
+            debug!("出现循环局面 {:?}", repetition);
+        }
+    }
 
     // 处理错误变化计数
-    fn handle_invalid_change(
-        &mut self, last_board: [[char; 9]; 10], board: [[char; 9]; 10], camp: &chess::Camp,
-    ) -> ChessboardState {
+    fn handle_invalid_change(&mut self, board: &chess::Board) -> ChessboardState {
         if self.invalid_change_count < 3 {
             self.invalid_change_count += 1;
-            let last_fen = chess::board_fen(camp, last_board);
-            let current = chess::board_fen(camp, board);
+            let last_fen = self.last_board.to_string();
+            let current = board.to_string();
             debug!("OneChanged last {}", last_fen);
             debug!("OneChanged current {}", current);
             ChessboardState::Invalid
@@ -128,36 +269,99 @@ impl AnalysisContext {
     }
 }
 
-pub fn get_board(image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> Option<(chess::Camp, [[char; 9]; 10])> {
+pub fn get_board(image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> Option<(chess::Camp, chess::Board)> {
     let data = predict(image).unwrap();
     if let Ok((camp, mut board)) = common::detections_to_board(&data) {
-        chess::board_fix(&camp, &mut board);
+        board.fix(&camp);
         Some((camp, board))
     } else {
         None
     }
 }
 
-pub fn analyse(app: &AppHandle, mut result: QueryResult, board: [[char; 9]; 10]) -> (chess::Changed, [[char; 9]; 10]) {
-    // 引擎结果翻译为中文
-    let best_pv = result.pvs.first().unwrap();
-    let best_move = chess::board_move_chinese(board, best_pv);
-    let expect_board = chess::board_move(board, best_pv);
-    let expect_move = chess::Changed::from_pv(best_pv, board);
-
-    let mut tmp_board = expect_board;
-    result.moves.push(best_move);
-    for pv in result.pvs.iter().skip(1).take(3) {
-        let mv = chess::board_move_chinese(tmp_board, pv);
-        result.moves.push(mv);
-        tmp_board = chess::board_move(tmp_board, pv);
+// 先查询云库, 失败时调用引擎, 引擎每完成一层的思考通过analyse_progress事件发送给前端
+// cancel被取消时停止搜索并返回None, stop_analyse只停止本次搜索, 无限分析时保留停止前的结果
+// ponder不为空时只用引擎后台思考, 对方走出预期应着后转为正常搜索
+fn search(
+    app: &AppHandle, board: &chess::Board, ponder: Option<&Ponder>, cancel: &CancellationToken,
+) -> Option<QueryResult> {
+    let state = SHARED_STATE.get().unwrap();
+    let (config, locale) = {
+        let config = state.config.read().unwrap();
+        (config.engine, config.notation)
+    };
+    let mut engine = state.engine.lock().unwrap();
+    // 拿到引擎后再登记停止信号, 此时之前的搜索已经结束
+    let stop = cancel.child_token();
+    *state.search_stop.lock().unwrap() = stop.clone();
+    let on_progress = |progress: &Progress| {
+        let mut progress = progress.clone();
+        progress.moves = chess::translate_pv(board, &progress.pvs[..progress.pvs.len().min(PV_MOVES)], locale).0;
+        app.emit("analyse_progress", progress).unwrap();
+    };
+    let result = match ponder {
+        Some(ponder) => block_on(engine.ponder(&ponder.start, &ponder.moves, &config, &ponder.hit, &stop, on_progress)),
+        None => block_on(engine.search_with_progress(&board.to_string(), &config, &stop, on_progress)),
+    };
+    result.filter(|_| !cancel.is_cancelled())
+}
+
+// 解析并校验FEN局面
+pub fn parse_position(fen: &str) -> Result<chess::Board, String> {
+    let board: chess::Board = fen.parse()?;
+    board.check_positions()?;
+    if chess::movegen::in_check(&board, board.turn.opponent()) {
+        return Err("非行棋方正被将军".to_string());
+    }
+    Ok(board)
+}
+
+// 分析任意局面, 与监听时的流程相同, 结果通过analyse事件发送给前端
+pub fn analyse_position(app: &AppHandle, fen: &str, cancel: &CancellationToken) -> Result<(), String> {
+    let board = parse_position(fen)?;
+    app.emit("mirror", false).unwrap();
+    app.emit("position", &board.map()).unwrap();
+    if let Some(result) = chess::movegen::game_over(&board) {
+        info!("对局结束 {:?}", result);
+        app.emit("gameover", result).unwrap();
+        return Ok(());
+    }
+    let Some(result) = search(app, &board, None, cancel) else {
+        // 被新的局面取代或手动停止时不算错误
+        let stopped = cancel.is_cancelled() || SHARED_STATE.get().unwrap().search_stop.lock().unwrap().is_cancelled();
+        return if stopped { Ok(()) } else { Err("未能获取分析结果".to_string()) };
+    };
+    let notation = SHARED_STATE.get().unwrap().config.read().unwrap().notation;
+    let history = chess::repetition::History::new(board);
+    analyse(app, result, board, &history, notation).ok_or("分析结果无效")?;
+    Ok(())
+}
+
+pub fn analyse(
+    app: &AppHandle, mut result: QueryResult, board: chess::Board, history: &chess::repetition::History,
+    locale: chess::Locale,
+) -> Option<(chess::Changed, chess::Board)> {
+    let best_pv = result.pvs.first()?.parse::<chess::Move>().ok()?;
+    let expect_board = board.make_move(best_pv);
+    let expect_move = chess::Changed::new(&board, best_pv)?;
+
+    // 建议的着法会形成长将、长捉等循环违例时提醒变着
+    if history.board().same_placement(&board) {
+        result.warning = history.forbidden(best_pv).map(|pattern| format!("{}违例, 需要变着", pattern.name()));
+    }
+
+    // 引擎结果和各候选变化翻译为中文和WXF记谱
+    (result.moves, result.wxf) = chess::translate_pv(&board, &result.pvs[..result.pvs.len().min(PV_MOVES)], locale);
+    for candidate in result.candidates.iter_mut() {
+        let pvs = &candidate.pvs[..candidate.pvs.len().min(PV_MOVES)];
+        (candidate.moves, candidate.wxf) = chess::translate_pv(&board, pvs, locale);
     }
     // 把结果发送给前端
     info!("分析结果 {:?}", result);
     app.emit("analyse", result).unwrap();
 
     // 返回一个预期move和预期board
-    (expect_move, expect_board)
+    Some((expect_move, expect_board))
 }
 
 // 处理循环逻辑的主函数
@@ -168,6 +372,7 @@ fn process_analysis_loop(mut context: AnalysisContext) {
         // 检查是否需要停止监听
         if context.should_stop() {
             debug!("listen stopped");
+            context.cancel_analysis();
             break;
         }
 
@@ -175,6 +380,13 @@ fn process_analysis_loop(mut context: AnalysisContext) {
         let interval = SHARED_STATE.get().unwrap().config.read().unwrap().timer_interval;
         thread::sleep(Duration::from_millis(interval));
 
+        // 后台分析完成时更新预期着法
+        if let Some(result) = context.poll_analysis() {
+            context.expect_move = Some(result.expect_move);
+            context.expect_board = result.expect_board;
+            context.ponder_move = result.ponder_move;
+        }
+
         // 捕获并分析棋盘
         let board_result = context.capture_and_analyze_board();
         if board_result.is_none() {
@@ -191,18 +403,16 @@ fn process_analysis_loop(mut context: AnalysisContext) {
                 debug!("首次启动，立即分析");
 
                 // 设置前端棋盘
-                context.update_ui(&camp, board);
+                context.update_ui(&camp, &board);
+                context.reset_game(board);
 
                 // 分析当前棋盘
-                if let Some(result) = context.analyze_board(&camp, board) {
-                    context.expect_move = result.expect_move;
-                    context.expect_board = result.expect_board;
-                }
+                context.analyze_board(board);
 
                 context.last_board = board;
 
                 // 如果是初始棋盘，进入初始状态，否则进入一般状态
-                if chess::startpos(board) {
+                if board.is_startpos() {
                     ChessboardState::StartPos
                 } else if camp.eq(&chess::Camp::Red) {
                     ChessboardState::OurTurn
@@ -213,37 +423,40 @@ fn process_analysis_loop(mut context: AnalysisContext) {
 
             ChessboardState::StartPos => {
                 // 判断棋盘是否仍然是初始棋盘
-                if !chess::startpos(board) {
+                if !board.is_startpos() {
                     // 不再是初始棋盘，处理正常的棋局变化
-                    if board == context.last_board {
+                    if board.same_placement(&context.last_board) {
                         ChessboardState::StartPos // 没有变化
                     } else {
                         // 有变化，更新UI并分析
-                        let (changed, board_state) = chess::board_diff(context.last_board, board);
-
-                        match board_state {
-                            chess::BoardChangeState::Move => {
+                        match context.last_board.diff(&board) {
+                            chess::BoardChangeState::Move(changed)
+                                if !chess::movegen::is_legal_move(
+                                    &context.last_board.with_turn(changed.camp),
+                                    changed.mv(),
+                                ) =>
+                            {
+                                debug!("不合法的走法 {}", changed.mv());
+                                context.handle_invalid_change(&board)
+                            }
+                            chess::BoardChangeState::Move(changed) => {
                                 context.last_board = board;
                                 context.handle_move(&changed);
 
-                                if camp.eq(&changed.camp) {
+                                if camp.color() == changed.camp {
                                     // 我方移动
+                                    context.check_game_over(&board.with_turn(camp.color().opponent()));
                                     ChessboardState::OurTurn
                                 } else {
                                     // 对方移动，需要分析
-                                    if let Some(result) = context.analyze_board(&camp, board) {
-                                        context.expect_move = result.expect_move;
-                                        context.expect_board = result.expect_board;
-                                    }
+                                    context.analyze_board(board);
                                     ChessboardState::OpponentTurn
                                 }
                             }
-                            chess::BoardChangeState::One => {
-                                context.handle_invalid_change(context.last_board, board, &camp)
-                            }
+                            chess::BoardChangeState::One => context.handle_invalid_change(&board),
                             chess::BoardChangeState::Unknown => {
                                 debug!("棋局变化未知，重置上下文");
-                                context.update_ui(&camp, board);
+                                context.update_ui(&camp, &board);
                                 context.last_board = board;
                                 ChessboardState::Initial
                             }
@@ -251,19 +464,17 @@ fn process_analysis_loop(mut context: AnalysisContext) {
                     }
                 } else if chess::Camp::Red.eq(&camp) {
                     // 仍然是初始棋盘，且我方先手
-                    if context.last_board == board {
+                    if context.last_board.same_placement(&board) {
                         // 防止重复分析
                         ChessboardState::StartPos
                     } else {
                         // 设置前端棋盘
                         context.last_board = board;
-                        context.update_ui(&camp, board);
+                        context.reset_game(board);
+                        context.update_ui(&camp, &board);
 
                         // 调用引擎查询
-                        if let Some(result) = context.analyze_board(&camp, board) {
-                            context.expect_move = result.expect_move;
-                            context.expect_board = result.expect_board;
-                        }
+                        context.analyze_board(board);
 
                         ChessboardState::OurTurn
                     }
@@ -271,23 +482,27 @@ fn process_analysis_loop(mut context: AnalysisContext) {
                     // 对方先手，跳过分析
                     debug!("对方先手，跳过分析");
                     context.last_board = board;
-                    context.update_ui(&camp, board);
+                    context.reset_game(board);
+                    context.update_ui(&camp, &board);
                     ChessboardState::OpponentTurn
                 }
             }
 
             ChessboardState::OurTurn | ChessboardState::OpponentTurn => {
                 // 判断棋盘是否未发生变化
-                if board == context.last_board {
+                if board.same_placement(&context.last_board) {
                     debug!("棋盘未发生变化，跳过分析");
                     current_state // 保持当前状态
-                } else if board == context.expect_board {
+                } else if board.same_placement(&context.expect_board) && context.expect_move.is_some() {
                     // 符合预期棋盘，跳过分析
                     debug!("棋盘为预期棋盘，跳过分析");
-                    let expect_move = context.expect_move.clone();
+                    let expect_move = context.expect_move.clone().unwrap();
                     let expect_board = context.expect_board;
                     context.last_board = expect_board;
                     context.handle_move(&expect_move);
+                    if !context.check_game_over(&expect_board.with_turn(expect_move.camp.opponent())) {
+                        context.start_ponder();
+                    }
 
                     // 更换下一个行动方
                     if current_state == ChessboardState::OurTurn {
@@ -297,45 +512,48 @@ fn process_analysis_loop(mut context: AnalysisContext) {
                     }
                 } else {
                     // 确认棋盘变化是否稳定
-                    if !context.confirm_board(board) {
+                    if !context.confirm_board(&board) {
                         debug!("棋盘延迟确认失败");
                         let confirm_interval = SHARED_STATE.get().unwrap().config.read().unwrap().confirm_interval;
                         thread::sleep(Duration::from_millis(confirm_interval));
                         current_state // 保持当前状态
-                    } else if !chess::board_check(board) {
+                    } else if !board.is_valid() {
                         // 检测棋盘是否有效
-                        let debug_fen = chess::board_fen(&camp, board);
+                        let debug_fen = board.to_string();
                         debug!("棋盘识别无效: {}", debug_fen);
                         current_state // 保持当前状态
                     } else {
                         // 处理正常棋盘变化
-                        let (changed, board_state) = chess::board_diff(context.last_board, board);
-
-                        match board_state {
-                            chess::BoardChangeState::Move => {
+                        match context.last_board.diff(&board) {
+                            chess::BoardChangeState::Move(changed)
+                                if !chess::movegen::is_legal_move(
+                                    &context.last_board.with_turn(changed.camp),
+                                    changed.mv(),
+                                ) =>
+                            {
+                                debug!("不合法的走法 {}", changed.mv());
+                                context.handle_invalid_change(&board)
+                            }
+                            chess::BoardChangeState::Move(changed) => {
                                 context.last_board = board;
                                 context.handle_move(&changed);
 
-                                if camp.eq(&changed.camp) {
+                                if camp.color() == changed.camp {
                                     // 我方移动，跳过分析
                                     debug!("我方移动, {} -> {}, 跳过分析", changed.from, changed.to);
+                                    context.check_game_over(&board.with_turn(camp.color().opponent()));
                                     ChessboardState::OurTurn
                                 } else {
                                     // 对方移动，需要分析
                                     debug!("对方移动, {} -> {}, 需要分析", changed.from, changed.to);
-                                    if let Some(result) = context.analyze_board(&camp, board) {
-                                        context.expect_move = result.expect_move;
-                                        context.expect_board = result.expect_board;
-                                    }
+                                    context.analyze_board(board);
                                     ChessboardState::OpponentTurn
                                 }
                             }
-                            chess::BoardChangeState::One => {
-                                context.handle_invalid_change(context.last_board, board, &camp)
-                            }
+                            chess::BoardChangeState::One => context.handle_invalid_change(&board),
                             chess::BoardChangeState::Unknown => {
                                 debug!("棋局变化未知，重置上下文");
-                                context.update_ui(&camp, board);
+                                context.update_ui(&camp, &board);
                                 context.last_board = board;
                                 ChessboardState::Initial
                             }
@@ -393,6 +611,39 @@ pub async fn start_listen(app: AppHandle, target: Window) -> Result<(), String>
     Ok(())
 }
 
+// 识别图片文件或剪贴板中的棋盘
+#[tauri::command]
+pub async fn recognize_image(source: recognize::ImageSource) -> Result<recognize::Recognition, String> {
+    let (camp, board) = recognize::recognize(source.load()?)?;
+    let fen = board.to_string();
+    debug!("recognize_image: {:?} {}", camp, fen);
+    Ok(recognize::Recognition { camp, board: board.map(), fen })
+}
+
+// 分析指定的FEN局面, 监听中时不可用
+#[tauri::command]
+pub async fn analyse_fen(app: AppHandle, fen: String) -> Result<(), String> {
+    debug!("analyse_fen: {}", fen);
+    let state = SHARED_STATE.get().unwrap();
+    if state.listen_thread.lock().unwrap().is_some() {
+        return Err("正在监听中, 请先停止监听".to_string());
+    }
+    // 载入新局面时取消之前的分析, 包括还在进行的无限分析
+    let cancel = CancellationToken::new();
+    std::mem::replace(&mut *state.analysis.lock().unwrap(), cancel.clone()).cancel();
+    // 引擎搜索是阻塞的, 放到单独的线程中执行
+    tauri::async_runtime::spawn_blocking(move || analyse_position(&app, &fen, &cancel))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 停止当前的引擎搜索, 无限分析时以停止前的思考作为结果
+#[tauri::command]
+pub fn stop_analyse() {
+    info!("stop analyse");
+    SHARED_STATE.get().unwrap().search_stop.lock().unwrap().cancel();
+}
+
 #[tauri::command]
 pub fn stop_listen() {
     info!("stop listen");
@@ -407,3 +658,20 @@ pub fn stop_listen() {
     }
     debug!("stoped");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_position() {
+        let board = parse_position("rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR b").unwrap();
+        assert_eq!(board.turn, chess::Color::Black);
+        assert!(parse_position("rnbakabnr/9/1c5c1/p1p1p1p1p/9/9").is_err());
+        // 黑象过河
+        assert!(parse_position("rnbaka1nr/9/1c5c1/p1p1p1p1p/9/4b4/P1P1P1P1P/1C5C1/9/RNBAKABNR w").is_err());
+        // 轮到红方走, 但黑将已被将军
+        assert!(parse_position("4k4/9/9/9/9/9/9/9/4R4/3K5 w").is_err());
+        assert!(parse_position("4k4/9/9/9/9/9/9/9/4R4/3K5 b").is_ok());
+    }
+}